@@ -0,0 +1,189 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use log::info;
+use reqwest::{header::HeaderValue, Client};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::{
+    config::{Hub, OAuth2, Secret},
+    hub::HubError,
+    logger,
+    report::ErrorClass,
+};
+
+/// Access tokens are renewed this long before they expire
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// `Authorization` header of the Hub requests
+#[derive(Clone)]
+pub enum Credentials {
+    Token(HeaderValue),
+    OAuth2(Arc<Session>),
+}
+
+impl Credentials {
+    /// # Errors
+    ///
+    /// Will return `Err` if no token is configured, it can't be read or it
+    /// is not a valid header value
+    pub fn new(hub: &Hub) -> Result<Self> {
+        if let Some(oauth2) = &hub.auth.oauth2 {
+            let client_secret = oauth2
+                .client_secret
+                .resolve()
+                .context("Unable to read hub.auth.oauth2.client_secret")?;
+            return Ok(Self::OAuth2(Arc::new(Session::new(
+                oauth2.clone(),
+                client_secret,
+            ))));
+        }
+        let token = token(hub)?;
+        let value = match &hub.auth.scheme {
+            Some(scheme) => format!("{scheme} {token}"),
+            None => token,
+        };
+        let mut header =
+            HeaderValue::from_str(&value).context("The Hub token is not a valid header value")?;
+        header.set_sensitive(true);
+        Ok(Self::Token(header))
+    }
+
+    /// Header of the next request, fetching an OAuth2 access token when there
+    /// is none or it is about to expire
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an access token can't be fetched
+    pub async fn header(&self, client: &Client) -> Result<HeaderValue, HubError> {
+        match self {
+            Self::Token(header) => Ok(header.clone()),
+            Self::OAuth2(session) => session.header(client).await,
+        }
+    }
+
+    /// Drops an access token refused by the Hub, the next request fetches a
+    /// new one
+    pub async fn refused(&self) {
+        if let Self::OAuth2(session) = self {
+            *session.token.lock().await = None;
+        }
+    }
+}
+
+/// Token from the config, or else from the environment variable or file,
+/// read as a [`Secret`] so that it is redacted from the logs
+fn token(hub: &Hub) -> Result<String> {
+    if let Some(token) = &hub.token {
+        token.resolve().context("Unable to read hub.token")
+    } else if let Some(var) = &hub.auth.token_env {
+        Secret::from(format!("env:{var}"))
+            .resolve()
+            .context("Unable to read hub.auth.token_env")
+    } else if let Some(path) = &hub.auth.token_file {
+        Secret::from(format!("file:{}", path.display()))
+            .resolve()
+            .context("Unable to read hub.auth.token_file")
+    } else {
+        bail!("No Hub token, set hub.token, hub.auth.token_env, hub.auth.token_file or hub.auth.oauth2")
+    }
+}
+
+/// Access token of the OAuth2 client credentials flow, shared by the clones
+/// of the Hub client
+pub struct Session {
+    config: OAuth2,
+    client_secret: String,
+    token: Mutex<Option<AccessToken>>,
+}
+
+struct AccessToken {
+    header: HeaderValue,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl Session {
+    fn new(config: OAuth2, client_secret: String) -> Self {
+        Self {
+            config,
+            client_secret,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn header(&self, client: &Client) -> Result<HeaderValue, HubError> {
+        let mut token = self.token.lock().await;
+        let expiring = |t: &AccessToken| {
+            t.expires_at
+                .is_some_and(|at| at <= Instant::now() + REFRESH_MARGIN)
+        };
+        let valid = token.as_ref().filter(|t| !expiring(t));
+        if let Some(t) = valid {
+            return Ok(t.header.clone());
+        }
+
+        let fresh = self.fetch(client).await?;
+        let header = fresh.header.clone();
+        *token = Some(fresh);
+        Ok(header)
+    }
+
+    async fn fetch(&self, client: &Client) -> Result<AccessToken, HubError> {
+        let c = &self.config;
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", c.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        if let Some(scope) = &c.scope {
+            form.push(("scope", scope));
+        }
+        if let Some(audience) = &c.audience {
+            form.push(("audience", audience));
+        }
+
+        let res = client
+            .post(c.token_url.clone())
+            .form(&form)
+            .send()
+            .await
+            .map_err(HubError::transport)?;
+        let status = res.status();
+        let headers = res.headers().clone();
+        let text = res.text().await.map_err(HubError::transport)?;
+        if !status.is_success() {
+            return Err(HubError::status(status, headers, &text));
+        }
+        let invalid =
+            || HubError::status(status, headers.clone(), &text).with_class(ErrorClass::Auth);
+        let body: TokenResponse = serde_json::from_str(&text).map_err(|_| invalid())?;
+        logger::redact(&body.access_token);
+        let mut header = HeaderValue::from_str(&format!("Bearer {}", body.access_token))
+            .map_err(|_| invalid())?;
+        header.set_sensitive(true);
+
+        info!(
+            "Fetched an OAuth2 access token from {}{}",
+            c.token_url,
+            body.expires_in
+                .map(|s| format!(", expiring in {s}s"))
+                .unwrap_or_default()
+        );
+        Ok(AccessToken {
+            header,
+            expires_at: body
+                .expires_in
+                .map(|s| Instant::now() + Duration::from_secs(s)),
+        })
+    }
+}
@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::{net::SocketAddr, path::PathBuf};
 
 use structopt::StructOpt;
+use url::Url;
+
+use crate::{logger::LogFormat, report::Format};
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(name = "benchy", about = "A CLI to benchmark Hub minting speed")]
@@ -10,6 +13,9 @@ pub struct Opt {
 
     #[structopt(flatten)]
     pub cmd: Cli,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -26,29 +32,166 @@ pub struct GlobalOptions {
     #[structopt(
         long,
         global = true,
-        help = "CSV report output path",
+        help = "Config profile merged over the base hub, mint and settings",
+        env = "CONFIG_PROFILE"
+    )]
+    pub profile: Option<String>,
+    #[structopt(
+        long,
+        global = true,
+        help = "Report output path",
         default_value = "./output.csv",
         env = "OUTPUT_PATH",
         parse(from_os_str)
     )]
     pub output: PathBuf,
+    #[structopt(
+        long,
+        global = true,
+        help = "Report format: csv, json or jsonl",
+        default_value = "csv",
+        env = "OUTPUT_FORMAT"
+    )]
+    pub format: Format,
+    #[structopt(
+        long,
+        global = true,
+        help = "Also write a self-contained HTML report with charts to this path",
+        env = "HTML_PATH",
+        parse(from_os_str)
+    )]
+    pub html: Option<PathBuf>,
+    #[structopt(
+        long,
+        global = true,
+        help = "Also write a Markdown summary of the run to this path",
+        env = "SUMMARY_MARKDOWN_PATH",
+        parse(from_os_str)
+    )]
+    pub summary_markdown: Option<PathBuf>,
+    #[structopt(
+        long,
+        global = true,
+        help = "Write the config assertions results as JUnit XML to this path",
+        env = "JUNIT_PATH",
+        parse(from_os_str)
+    )]
+    pub junit: Option<PathBuf>,
+    #[structopt(
+        long,
+        global = true,
+        help = "Run history database path",
+        default_value = "./benchy.db",
+        env = "HISTORY_PATH",
+        parse(from_os_str)
+    )]
+    pub history: PathBuf,
+    #[structopt(
+        long,
+        global = true,
+        help = "Do not store the run in the history database"
+    )]
+    pub no_history: bool,
+    #[structopt(
+        long,
+        global = true,
+        help = "Log format: text, or json for one object per event",
+        default_value = "text",
+        env = "LOG_FORMAT"
+    )]
+    pub log_format: LogFormat,
 }
 
 #[derive(StructOpt, Debug, Default, Clone)]
 pub struct Cli {
-    /// Number of concurrent requests
-    #[structopt(short, long, default_value = "1")]
-    pub parallelism: usize,
+    /// Number of concurrent requests, 1 by default
+    #[structopt(short, long)]
+    pub parallelism: Option<usize>,
 
-    /// Number of iterations to run
-    #[structopt(short, long, default_value = "1")]
-    pub iterations: usize,
+    /// Number of iterations to run, 1 by default
+    #[structopt(short, long)]
+    pub iterations: Option<usize>,
 
-    /// Wait Delay in seconds between each iteration
-    #[structopt(short, long, default_value = "1")]
-    pub delay: u64,
+    /// Wait Delay in seconds between each iteration, 1 by default
+    #[structopt(short, long)]
+    pub delay: Option<u64>,
 
     /// Wait Delay in seconds between each iteration
     #[structopt(short, long)]
     pub retry: bool,
+
+    /// Serve Prometheus metrics on this address during the run, e.g. 127.0.0.1:9100
+    #[structopt(long)]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Export a trace of every mint to this OTLP/HTTP collector, e.g. http://localhost:4318
+    #[structopt(long)]
+    pub otlp_endpoint: Option<Url>,
+
+    /// Log periodic status lines instead of progress bars, the default when not on a terminal
+    #[structopt(long)]
+    pub headless: bool,
+
+    /// Seconds between headless status lines
+    #[structopt(long)]
+    pub status_interval: Option<u64>,
+
+    /// Show a full-screen dashboard to pause submission, adjust the rate or abort the run
+    #[structopt(long)]
+    pub tui: bool,
+
+    /// Maximum number of retries per mint
+    #[structopt(long)]
+    pub max_retries: Option<u64>,
+
+    /// Credits the run may spend, a larger estimate needs a confirmation
+    #[structopt(long)]
+    pub budget: Option<u64>,
+
+    /// Start without confirmation when the estimated cost exceeds the budget
+    #[structopt(short, long)]
+    pub yes: bool,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub enum Command {
+    /// Browse and manage past runs stored in the history database
+    History(History),
+    /// Write the reports of a past run, using the global report options
+    Report {
+        /// Run id, or a unique prefix of it
+        run: String,
+    },
+    /// Compare a candidate run against a baseline using the config
+    /// `regression` tolerances
+    Compare {
+        /// Report file (json, jsonl or csv) or history run id
+        baseline: String,
+        /// Report file (json, jsonl or csv) or history run id
+        candidate: String,
+    },
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub enum History {
+    /// List past runs, most recent first
+    List {
+        /// Maximum number of runs to list
+        #[structopt(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Only list runs whose config fingerprint starts with this
+        #[structopt(long)]
+        fingerprint: Option<String>,
+    },
+    /// Show the summary of a past run
+    Show {
+        /// Run id, or a unique prefix of it
+        run: String,
+    },
+    /// Delete a past run and its records
+    Delete {
+        /// Run id, or a unique prefix of it
+        run: String,
+    },
 }
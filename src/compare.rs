@@ -0,0 +1,255 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    config::Regression,
+    csv::Row,
+    history::History,
+    report::{Record, Run},
+    slo::Check,
+    stats::{self, MannWhitney, Summary},
+};
+
+pub const DEFAULT_ALPHA: f64 = 0.05;
+
+/// Records and summary of a run, wherever they were loaded from
+pub struct ResultSet {
+    pub label: String,
+    pub summary: Summary,
+    pub records: Vec<Record>,
+}
+
+impl ResultSet {
+    /// Loads a report file, detected by its `json`, `jsonl` or `csv`
+    /// extension, or else a run id from the history
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the source can't be read or parsed, or is neither
+    /// a file nor a run of an existing history
+    pub fn load(source: &str, history: &Path) -> Result<Self> {
+        let path = Path::new(source);
+        if !path.is_file() {
+            // Opening the history would create it
+            if !history.is_file() {
+                return Err(anyhow!(
+                    "No report file {source}, and no history at {} to find it as a run id",
+                    history.display()
+                ));
+            }
+            let run = History::open(history)?.load(source)?;
+            return Ok(Self::from_run(run));
+        }
+
+        let content = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let records = match extension.as_str() {
+            "json" => return Ok(Self::from_run(serde_json::from_str::<Run>(&content)?)),
+            "jsonl" => content
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(serde_json::from_str::<Record>)
+                .collect::<Result<Vec<_>, _>>()?,
+            "csv" => ::csv::Reader::from_reader(content.as_bytes())
+                .deserialize::<Row>()
+                .map(|row| row.map(Record::from))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(anyhow!("Unknown report format for {source}")),
+        };
+
+        Ok(Self {
+            label: source.to_string(),
+            summary: Summary::new(&records, stats::span(&records)),
+            records,
+        })
+    }
+
+    fn from_run(run: Run) -> Self {
+        Self {
+            label: run.metadata.run_id.to_string(),
+            summary: run.summary,
+            records: run.records,
+        }
+    }
+
+    fn completion(&self) -> Vec<f64> {
+        self.records
+            .iter()
+            .filter(|r| r.success)
+            .map(Record::completion_secs)
+            .collect()
+    }
+}
+
+pub struct Comparison {
+    pub baseline: ResultSet,
+    pub candidate: ResultSet,
+    /// Whether the candidate completion times are larger than the baseline's
+    pub mann_whitney: Option<MannWhitney>,
+}
+
+impl Comparison {
+    pub fn new(baseline: ResultSet, candidate: ResultSet) -> Self {
+        let mann_whitney = MannWhitney::new(&baseline.completion(), &candidate.completion());
+        Self {
+            baseline,
+            candidate,
+            mann_whitney,
+        }
+    }
+
+    pub fn significant(&self, alpha: f64) -> bool {
+        self.mann_whitney
+            .as_ref()
+            .is_some_and(|mw| mw.p_value < alpha)
+    }
+
+    pub fn print(&self, alpha: f64) {
+        let (b, c) = (&self.baseline.summary, &self.candidate.summary);
+        println!("baseline:  {} ({} mints)", self.baseline.label, b.total);
+        println!("candidate: {} ({} mints)\n", self.candidate.label, c.total);
+        println!(
+            "{:<14}  {:>12}  {:>12}  {:>10}",
+            "METRIC", "BASELINE", "CANDIDATE", "DELTA"
+        );
+        println!(
+            "{:<14}  {:>11.1}%  {:>11.1}%  {:>+8.1}pp",
+            "success rate",
+            b.success_rate * 100.0,
+            c.success_rate * 100.0,
+            (c.success_rate - b.success_rate) * 100.0
+        );
+        println!(
+            "{:<14}  {:>10.3}/s  {:>10.3}/s  {:>10}",
+            "throughput",
+            b.throughput,
+            c.throughput,
+            delta(b.throughput, c.throughput)
+        );
+
+        let percentiles = |s: &Summary| s.completion.as_ref().map(|p| [p.p50, p.p90, p.p95, p.p99]);
+        if let (Some(bp), Some(cp)) = (percentiles(b), percentiles(c)) {
+            for (i, name) in ["p50", "p90", "p95", "p99"].iter().enumerate() {
+                println!(
+                    "{:<14}  {:>11.2}s  {:>11.2}s  {:>10}",
+                    format!("completion {name}"),
+                    bp[i],
+                    cp[i],
+                    delta(bp[i], cp[i])
+                );
+            }
+        }
+
+        match &self.mann_whitney {
+            Some(mw) => println!(
+                "\nMann-Whitney U = {:.1}, z = {:.3}, one-sided p = {:.4}: candidate is {}slower at alpha {alpha}",
+                mw.u,
+                mw.z,
+                mw.p_value,
+                if mw.p_value < alpha { "significantly " } else { "not significantly " }
+            ),
+            None => println!("\nMann-Whitney U test skipped, not enough created mints"),
+        }
+    }
+}
+
+/// Checks the candidate against the configured tolerances
+pub fn evaluate(regression: &Regression, cmp: &Comparison) -> Vec<Check> {
+    let (b, c) = (&cmp.baseline.summary, &cmp.candidate.summary);
+    let alpha = regression.alpha.unwrap_or(DEFAULT_ALPHA);
+    let significant = cmp.significant(alpha) || !regression.require_significance.unwrap_or(true);
+    let mut checks = Vec::new();
+
+    if let Some(max) = regression.max_success_rate_drop {
+        let drop = b.success_rate - c.success_rate;
+        checks.push(Check {
+            name: "max_success_rate_drop".to_string(),
+            passed: drop <= max,
+            message: format!(
+                "success rate dropped {:.2} points (maximum {:.2})",
+                drop * 100.0,
+                max * 100.0
+            ),
+        });
+    }
+
+    if let Some(max) = regression.max_throughput_drop {
+        let drop = relative(b.throughput, c.throughput).map(|d| -d);
+        checks.push(Check {
+            name: "max_throughput_drop".to_string(),
+            passed: !drop.is_some_and(|d| d > max),
+            message: match drop {
+                Some(d) => format!(
+                    "throughput dropped {:.1}% (maximum {:.1}%)",
+                    d * 100.0,
+                    max * 100.0
+                ),
+                None => "baseline has no throughput to compare with".to_string(),
+            },
+        });
+    }
+
+    let latency = [
+        (
+            "max_p50_increase",
+            regression.max_p50_increase,
+            b.completion.as_ref().map(|p| p.p50),
+            c.completion.as_ref().map(|p| p.p50),
+        ),
+        (
+            "max_p95_increase",
+            regression.max_p95_increase,
+            b.completion.as_ref().map(|p| p.p95),
+            c.completion.as_ref().map(|p| p.p95),
+        ),
+    ];
+    for (name, max, baseline, candidate) in latency {
+        let Some(max) = max else { continue };
+        let check = match (baseline, candidate) {
+            (Some(bv), Some(cv)) => {
+                let increase = relative(bv, cv).unwrap_or_default();
+                let exceeded = increase > max;
+                Check {
+                    name: name.to_string(),
+                    passed: !exceeded || !significant,
+                    message: format!(
+                        "completion time increased {:.1}% (maximum {:.1}%){}",
+                        increase * 100.0,
+                        max * 100.0,
+                        if exceeded && !significant {
+                            ", not significant"
+                        } else {
+                            ""
+                        }
+                    ),
+                }
+            },
+            (Some(_), None) => Check {
+                name: name.to_string(),
+                passed: false,
+                message: "candidate has no created mint".to_string(),
+            },
+            (None, _) => Check {
+                name: name.to_string(),
+                passed: true,
+                message: "baseline has no created mint to compare with".to_string(),
+            },
+        };
+        checks.push(check);
+    }
+
+    checks
+}
+
+fn relative(baseline: f64, candidate: f64) -> Option<f64> {
+    (baseline > 0.0).then(|| (candidate - baseline) / baseline)
+}
+
+fn delta(baseline: f64, candidate: f64) -> String {
+    relative(baseline, candidate).map_or_else(|| "-".to_string(), |d| format!("{:+.1}%", d * 100.0))
+}
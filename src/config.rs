@@ -1,18 +1,41 @@
-use std::{fs, io, path::PathBuf, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
 
-use anyhow::Result;
-use log::error;
+use anyhow::{anyhow, Context, Result};
+use log::{error, warn};
 use once_cell::sync::OnceCell;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value as Json};
 use url::Url;
+use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
+use crate::{logger, report::ErrorClass};
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// Profile selected with `--profile`, its sections were merged over the
+    /// base ones
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Secrets written in the files the config was read from, by file
+    #[serde(skip)]
+    literal_secrets: Vec<(PathBuf, Vec<String>)>,
     pub hub: Hub,
     pub settings: Settings,
     pub mint: MintConfig,
+    #[serde(default)]
+    pub assertions: Assertions,
+    #[serde(default)]
+    pub regression: Regression,
+    pub push: Option<PushConfig>,
 }
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Settings {
     pub parallelism: Option<usize>,
     pub iterations: Option<usize>,
@@ -21,14 +44,208 @@ pub struct Settings {
     pub log_level: Option<String>,
     pub timeout: Option<u64>,
     pub retry_delay: Option<u64>,
+    pub metrics_addr: Option<SocketAddr>,
+    pub otlp_endpoint: Option<Url>,
+    /// Log status lines instead of drawing progress bars, by default when
+    /// not running on a terminal
+    pub headless: Option<bool>,
+    /// Seconds between headless status lines, at least 1, 10 by default
+    pub status_interval: Option<u64>,
+    /// Show the interactive dashboard instead of progress bars
+    pub tui: Option<bool>,
+    /// Retries allowed per mint, unbounded by default
+    pub max_retries: Option<u64>,
+    /// Credits the run may spend, a larger estimate needs a confirmation
+    pub budget: Option<u64>,
 }
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Hub {
     pub url: Url,
-    pub token: String,
+    /// API token, or see `auth` for the other ways to authenticate
+    pub token: Option<Secret>,
+    #[serde(default)]
+    pub auth: Auth,
+    /// Extra headers sent with every request, e.g. a tenant or an API key
+    #[serde(default)]
+    pub headers: BTreeMap<String, Secret>,
+    /// Organization whose credit balance is snapshotted before and after the
+    /// run
+    pub organization: Option<Uuid>,
+    #[serde(default)]
+    pub backoff: Backoff,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    #[serde(default)]
+    pub transport: Transport,
+    pub proxy: Option<Proxy>,
+    #[serde(default)]
+    pub tls: Tls,
+}
+
+/// How the Hub requests are authenticated when `hub.token` is not set
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Auth {
+    /// Scheme put before the token in the `Authorization` header, e.g.
+    /// `Bearer`, none by default
+    pub scheme: Option<String>,
+    /// Environment variable holding the token
+    pub token_env: Option<String>,
+    /// File holding the token
+    pub token_file: Option<PathBuf>,
+    /// Client credentials exchanged for Bearer access tokens
+    pub oauth2: Option<OAuth2>,
+}
+
+/// OAuth2 client credentials flow, the access token is fetched again before
+/// it expires or when the Hub refuses it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OAuth2 {
+    pub token_url: Url,
+    pub client_id: String,
+    pub client_secret: Secret,
+    pub scope: Option<String>,
+    pub audience: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Proxy of the Hub requests. Without one, the `HTTPS_PROXY`, `HTTP_PROXY`
+/// and `NO_PROXY` environment variables apply.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Proxy {
+    /// `http://` or `https://` url of the proxy, without credentials
+    pub url: Url,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    /// Comma separated hosts, domains and IP ranges reached without the proxy
+    pub no_proxy: Option<String>,
+}
+
+/// Certificates of the Hub connections, on top of the built-in roots
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Tls {
+    /// PEM files of extra root certificates, e.g. an internal CA
+    #[serde(default)]
+    pub ca_certs: Vec<PathBuf>,
+    /// PEM file of the client certificate chain for mTLS
+    pub client_cert: Option<PathBuf>,
+    /// PEM file of the client private key, if not in `client_cert`
+    pub client_key: Option<PathBuf>,
+    /// Accepts any server certificate, only meant for local stand-ins of
+    /// the Hub
+    #[serde(default)]
+    pub dangerous_accept_invalid_certs: bool,
+}
+
+/// Connections of the Hub requests, the idle timeout is set per request kind
+/// in [`Timeouts`]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Transport {
+    /// `auto` by default
+    pub http_version: Option<HttpVersion>,
+    /// Idle connections kept per host, unlimited by default
+    pub pool_max_idle: Option<usize>,
+    /// Sends small packets without waiting, true by default
+    pub tcp_nodelay: Option<bool>,
+    /// Interval of the TCP keep-alive probes in milliseconds, off by default
+    pub tcp_keepalive_ms: Option<u64>,
+    /// Opens a new connection for every request, false by default
+    pub fresh_connections: Option<bool>,
+}
+
+impl Transport {
+    /// Transport with its defaults filled in, as recorded in the run metadata
+    pub fn resolved(&self) -> Self {
+        Self {
+            http_version: Some(self.http_version.unwrap_or_default()),
+            pool_max_idle: self.pool_max_idle,
+            tcp_nodelay: Some(self.tcp_nodelay.unwrap_or(true)),
+            tcp_keepalive_ms: self.tcp_keepalive_ms,
+            fresh_connections: Some(self.fresh_connections.unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t = self.resolved();
+        write!(f, "{}", t.http_version.unwrap_or_default())?;
+        if t.fresh_connections.unwrap_or_default() {
+            write!(f, ", fresh connections")?;
+        } else if let Some(max) = t.pool_max_idle {
+            write!(f, ", {max} idle connections")?;
+        }
+        if !t.tcp_nodelay.unwrap_or(true) {
+            write!(f, ", no TCP nodelay")?;
+        }
+        if let Some(ms) = t.tcp_keepalive_ms {
+            write!(f, ", keep-alive {ms}ms")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersion {
+    /// HTTP/1.1, or HTTP/2 when negotiated with TLS
+    #[default]
+    Auto,
+    /// HTTP/1.1 only
+    Http1,
+    /// HTTP/2 without negotiation, the Hub must support it
+    Http2,
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Auto => "auto",
+            Self::Http1 => "http1",
+            Self::Http2 => "http2",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Timeouts of the Hub requests. Mutations and queries each have their own
+/// connections, so a slow mint doesn't hold up the status polls.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Timeouts {
+    /// Mint and retry mutations, the request timeout is 60000 by default
+    #[serde(default)]
+    pub mutation: RequestTimeouts,
+    /// Status and other queries, the request timeout is 30000 by default
+    #[serde(default)]
+    pub query: RequestTimeouts,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RequestTimeouts {
+    /// Establishing a connection, in milliseconds, 10000 by default
+    pub connect_ms: Option<u64>,
+    /// Whole request until its response is read, in milliseconds
+    pub request_ms: Option<u64>,
+    /// Unused pooled connections are closed after this many milliseconds,
+    /// 90000 by default
+    pub idle_ms: Option<u64>,
+}
+
+/// Backoff of the requests throttled by the Hub with HTTP 429 or 503, the
+/// `Retry-After` delay is used instead when the Hub sends one
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Backoff {
+    /// Attempts of a throttled request before it fails, 5 by default
+    pub max_attempts: Option<u32>,
+    /// Delay before the second attempt in milliseconds, doubled at every
+    /// attempt, 500 by default
+    pub initial_ms: Option<u64>,
+    /// Longest delay between attempts in milliseconds, `Retry-After` included,
+    /// 30000 by default
+    pub max_ms: Option<u64>,
+    /// Randomized share of the delay, between 0 and 1, 0.5 by default
+    pub jitter: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MintConfig {
     pub collection_id: String,
     pub recipient: String,
@@ -36,28 +253,200 @@ pub struct MintConfig {
     pub description: String,
     pub compressed: bool,
     pub image: String,
+    /// Blockchain of the collection used to price the mints, `SOLANA` by
+    /// default
+    pub blockchain: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CreatorConfig {
     pub address: String,
     pub verified: bool,
 }
 
+/// Thresholds checked once every mint is settled, a violation makes benchy
+/// exit with a non-zero code
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Assertions {
+    /// Minimum ratio of created mints, between 0 and 1
+    pub min_success_rate: Option<f64>,
+    /// Maximum p95 completion time of created mints, in seconds
+    pub max_p95_completion: Option<f64>,
+    /// Maximum ratio of mints failing with each error class, between 0 and 1
+    #[serde(default)]
+    pub max_error_rate: BTreeMap<ErrorClass, f64>,
+}
+
+/// Tolerances used by `benchy compare`, a candidate exceeding any of them makes
+/// benchy exit with a non-zero code
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Regression {
+    /// Maximum drop of the success rate, as a ratio (0.05 is 5 points)
+    pub max_success_rate_drop: Option<f64>,
+    /// Maximum relative throughput drop (0.1 is 10% slower)
+    pub max_throughput_drop: Option<f64>,
+    /// Maximum relative increase of the p50 completion time
+    pub max_p50_increase: Option<f64>,
+    /// Maximum relative increase of the p95 completion time
+    pub max_p95_increase: Option<f64>,
+    /// Significance level of the Mann-Whitney U test, 0.05 by default
+    pub alpha: Option<f64>,
+    /// Only fail on latency tolerances when the Mann-Whitney U test finds the
+    /// candidate significantly slower, true by default
+    pub require_significance: Option<bool>,
+}
+
+/// Where to push summary metrics during and after the run
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PushConfig {
+    pub kind: PushKind,
+    /// Pushgateway base url, or the full InfluxDB write url including the
+    /// database or bucket parameters
+    pub url: Url,
+    /// Seconds between periodic pushes, 30 by default
+    pub interval: Option<u64>,
+    pub workload: Option<String>,
+    pub environment: Option<String>,
+    /// InfluxDB API token
+    pub token: Option<Secret>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PushKind {
+    Pushgateway,
+    Influx,
+}
+
+/// Secret of the config, either literal or `env:VAR`, `file:/path` or
+/// `cmd:command` to read it from an environment variable, a file or the
+/// standard output of a command. Literal secrets are never serialized.
+#[derive(Clone, Deserialize)]
+#[serde(from = "String")]
+pub struct Secret(String);
+
+const SECRET_SOURCES: &[&str] = &["env:", "file:", "cmd:"];
+
+impl Secret {
+    pub fn is_literal(&self) -> bool {
+        !SECRET_SOURCES.iter().any(|p| self.0.starts_with(p))
+    }
+
+    /// Reads the secret from its source, it is redacted from the logs from
+    /// then on
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the variable is not set, the file can't be read
+    /// or the command fails
+    pub fn resolve(&self) -> Result<String> {
+        let value = if let Some(var) = self.0.strip_prefix("env:") {
+            std::env::var(var).with_context(|| format!("Environment variable {var} is not set"))?
+        } else if let Some(path) = self.0.strip_prefix("file:") {
+            fs::read_to_string(path).with_context(|| format!("Unable to read {path}"))?
+        } else if let Some(cmd) = self.0.strip_prefix("cmd:") {
+            let (shell, flag) = if cfg!(windows) {
+                ("cmd", "/C")
+            } else {
+                ("sh", "-c")
+            };
+            let out = Command::new(shell)
+                .args([flag, cmd])
+                .output()
+                .with_context(|| format!("Unable to run `{cmd}`"))?;
+            if !out.status.success() {
+                return Err(anyhow!("`{cmd}` failed with {}", out.status));
+            }
+            String::from_utf8(out.stdout)
+                .with_context(|| format!("`{cmd}` printed invalid UTF-8"))?
+        } else {
+            self.0.clone()
+        };
+        let value = value.trim().to_string();
+        logger::redact(&value);
+        Ok(value)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_literal() {
+            f.write_str(logger::REDACTED)
+        } else {
+            f.write_str(&self.0)
+        }
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({self})")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 static CONFIG: OnceCell<Arc<Config>> = OnceCell::new();
 
 impl Config {
+    /// Loads the config file and its includes. The sections of the `profiles`
+    /// entry named `profile`, and of the profiles it `extends`, are merged
+    /// over the base sections, then `${VAR}` and `${VAR:-default}` are
+    /// replaced in the string values.
+    ///
     /// # Errors
     ///
-    /// Will return `Err` if unable to read config file
-    pub fn load(path: &PathBuf) -> Result<(), io::Error> {
-        let config = serde_json::from_str::<Config>(&fs::read_to_string(path)?)?;
+    /// Will return `Err` if a file can't be read or parsed, the profile is
+    /// unknown or an interpolated variable is not set
+    pub fn load(path: &Path, profile: Option<&str>) -> Result<()> {
+        let mut literal_secrets = Vec::new();
+        let mut value = read(path, &mut Vec::new(), &mut literal_secrets)?;
+        let profiles = match value.as_object_mut().and_then(|c| c.remove("profiles")) {
+            Some(Json::Object(profiles)) => profiles,
+            Some(_) => return Err(anyhow!("profiles must be an object of named profiles")),
+            None => Map::new(),
+        };
+        if let Some(name) = profile {
+            for overrides in lineage(&profiles, name)?.into_iter().rev() {
+                merge(&mut value, overrides);
+            }
+        }
+        expand(&mut value, "")?;
+
+        let mut config: Config = serde_json::from_value(value)?;
+        config.profile = profile.map(str::to_string);
+        config.literal_secrets = literal_secrets;
         CONFIG
             .set(Arc::new(config))
-            .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "Config already loaded"))?;
+            .map_err(|_| anyhow!("Config already loaded"))?;
         Ok(())
     }
 
+    /// Warns about the config files holding a literal secret that other
+    /// users can read
+    pub fn check_secrets(&self) {
+        for (path, literals) in &self.literal_secrets {
+            if world_readable(path) {
+                warn!(
+                    "{} is readable by other users and holds a literal {}, restrict its \
+                     permissions or use env:, file: or cmd: sources",
+                    path.display(),
+                    literals.join(", ")
+                );
+            }
+        }
+    }
+
     pub fn read() -> &'static Config {
         CONFIG.get().map_or_else(
             || {
@@ -70,14 +459,428 @@ impl Config {
 }
 
 impl Settings {
+    /// Merges the command line over the config settings, those of the
+    /// profile included
     pub fn merge(self, cli: &crate::Opt) -> Self {
         let mut settings = self;
         let cmd = cli.cmd.clone();
 
-        settings.parallelism = Some(cmd.parallelism).or(settings.parallelism);
-        settings.iterations = Some(cmd.iterations).or(settings.iterations);
-        settings.delay = Some(cmd.delay).or(settings.delay);
-        settings.retry = Some(cmd.retry).or(settings.retry);
+        settings.parallelism = cmd.parallelism.or(settings.parallelism).or(Some(1));
+        settings.iterations = cmd.iterations.or(settings.iterations).or(Some(1));
+        settings.delay = cmd.delay.or(settings.delay).or(Some(1));
+        settings.retry = cmd.retry.then_some(true).or(settings.retry).or(Some(false));
+        settings.metrics_addr = cmd.metrics_addr.or(settings.metrics_addr);
+        settings.otlp_endpoint = cmd.otlp_endpoint.or(settings.otlp_endpoint);
+        settings.headless = cmd.headless.then_some(true).or(settings.headless);
+        settings.status_interval = cmd.status_interval.or(settings.status_interval);
+        settings.tui = cmd.tui.then_some(true).or(settings.tui);
+        settings.max_retries = cmd.max_retries.or(settings.max_retries);
+        settings.budget = cmd.budget.or(settings.budget);
         settings
     }
 }
+
+/// Keys holding secrets, in the base sections or a profile
+const SECRET_KEYS: &[&str] = &[
+    "hub.token",
+    "hub.auth.oauth2.client_secret",
+    "hub.proxy.password",
+    "push.token",
+];
+
+/// Keys holding numbers or booleans, `*` matching any key. Every other value
+/// stays a string once interpolated, whatever the variable holds.
+const TYPED_KEYS: &[&str] = &[
+    "settings.*",
+    "hub.backoff.*",
+    "hub.timeouts.*.*",
+    "hub.transport.*",
+    "hub.tls.dangerous_accept_invalid_certs",
+    "mint.compressed",
+    "mint.creator.verified",
+    "assertions.*",
+    "assertions.max_error_rate.*",
+    "regression.*",
+    "push.interval",
+];
+
+/// Whether the dotted `key` matches `pattern`
+fn matches_key(pattern: &str, key: &str) -> bool {
+    pattern.split('.').count() == key.split('.').count()
+        && pattern
+            .split('.')
+            .zip(key.split('.'))
+            .all(|(p, k)| p == "*" || p == k)
+}
+
+/// Reads a config file, YAML or TOML by their extension and JSON otherwise,
+/// merged over the files it `include`s. `chain` holds the including files,
+/// and the files holding literal secrets are added to `literal_secrets`.
+fn read(
+    path: &Path,
+    chain: &mut Vec<PathBuf>,
+    literal_secrets: &mut Vec<(PathBuf, Vec<String>)>,
+) -> Result<Json> {
+    let canonical = fs::canonicalize(path)
+        .with_context(|| format!("Unable to read config {}", path.display()))?;
+    if chain.contains(&canonical) {
+        return Err(anyhow!("Config {} includes itself", path.display()));
+    }
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read config {}", path.display()))?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    let parsed = match extension.as_deref() {
+        Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(anyhow::Error::from),
+        Some("toml") => toml::from_str(&text).map_err(anyhow::Error::from),
+        _ => serde_json::from_str(&text).map_err(anyhow::Error::from),
+    };
+    let mut value: Json =
+        parsed.with_context(|| format!("Unable to parse config {}", path.display()))?;
+    let literals = literals(&value);
+    if !literals.is_empty() {
+        literal_secrets.push((path.to_path_buf(), literals));
+    }
+
+    let includes = match value.as_object_mut().and_then(|c| c.remove("include")) {
+        None => return Ok(value),
+        Some(Json::String(include)) => vec![Json::String(include)],
+        Some(Json::Array(includes)) => includes,
+        Some(_) => {
+            return Err(anyhow!(
+                "include of {} must be a path or a list of paths",
+                path.display()
+            ))
+        },
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut base = Json::Object(Map::new());
+    chain.push(canonical);
+    for include in includes {
+        let Json::String(include) = include else {
+            return Err(anyhow!(
+                "include of {} must be a path or a list of paths",
+                path.display()
+            ));
+        };
+        let include = interpolate(&include)
+            .with_context(|| format!("Unable to interpolate include {include}"))?;
+        merge(&mut base, read(&dir.join(include), chain, literal_secrets)?);
+    }
+    chain.pop();
+    merge(&mut base, value);
+    Ok(base)
+}
+
+/// Secret keys of a config file whose value is the secret itself, rather than
+/// where to read it from
+fn literals(value: &Json) -> Vec<String> {
+    let mut sections = vec![(String::new(), value)];
+    if let Some(Json::Object(profiles)) = value.get("profiles") {
+        sections.extend(
+            profiles
+                .iter()
+                .map(|(name, profile)| (format!("profiles.{name}."), profile)),
+        );
+    }
+    let mut literals = Vec::new();
+    for (prefix, section) in sections {
+        for key in SECRET_KEYS {
+            let secret = key
+                .split('.')
+                .try_fold(section, |v, k| v.get(k))
+                .and_then(Json::as_str);
+            if secret.is_some_and(is_literal) {
+                literals.push(format!("{prefix}{key}"));
+            }
+        }
+    }
+    literals
+}
+
+/// Whether a raw secret value is written in the file. Of a value
+/// interpolated from the environment, only the defaults are.
+fn is_literal(secret: &str) -> bool {
+    if secret.contains("${") {
+        return secret.split("${").skip(1).any(|placeholder| {
+            placeholder
+                .split_once('}')
+                .and_then(|(expr, _)| expr.split_once(":-"))
+                .is_some_and(|(_, default)| !default.is_empty())
+        });
+    }
+    Secret::from(secret.to_string()).is_literal()
+}
+
+/// Interpolates the environment in the string values under `value`, `key`
+/// being its dotted path. Under the [`TYPED_KEYS`], a value made of a single
+/// placeholder whose variable holds a number or a boolean becomes that
+/// number or boolean.
+fn expand(value: &mut Json, key: &str) -> Result<()> {
+    match value {
+        Json::String(s) => {
+            let typed = s.starts_with("${")
+                && s.ends_with('}')
+                && s.matches("${").count() == 1
+                && TYPED_KEYS.iter().any(|pattern| matches_key(pattern, key));
+            let expanded =
+                interpolate(s).with_context(|| format!("Unable to interpolate {key}"))?;
+            *value = match serde_json::from_str(&expanded) {
+                Ok(parsed @ (Json::Number(_) | Json::Bool(_))) if typed => parsed,
+                _ => Json::String(expanded),
+            };
+        },
+        Json::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand(item, &format!("{key}[{i}]"))?;
+            }
+        },
+        Json::Object(entries) => {
+            for (k, v) in entries.iter_mut() {
+                let key = if key.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{key}.{k}")
+                };
+                expand(v, &key)?;
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Replaces `${VAR}` with the variable and `${VAR:-default}` with the
+/// variable, or `default` when it is unset or empty. `$${` is a literal `${`.
+fn interpolate(s: &str) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+        let Some(after) = rest.strip_prefix("${") else {
+            out.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow!("Unclosed ${{ placeholder"))?;
+        let (name, default) = match after[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&after[..end], None),
+        };
+        match (std::env::var(name), default) {
+            (Ok(value), None) => out.push_str(&value),
+            (Ok(value), Some(_)) if !value.is_empty() => out.push_str(&value),
+            (_, Some(default)) => out.push_str(default),
+            (Err(_), None) => return Err(anyhow!("Environment variable {name} is not set")),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Sections of the profile `name` then of the profiles it extends, without
+/// their `extends` key
+fn lineage(profiles: &Map<String, Json>, name: &str) -> Result<Vec<Json>> {
+    let mut lineage = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    let mut next = Some(name.to_string());
+    while let Some(name) = next {
+        if seen.contains(&name) {
+            return Err(anyhow!(
+                "Profile {name} extends itself: {} -> {name}",
+                seen.join(" -> ")
+            ));
+        }
+        let mut profile = match profiles.get(&name) {
+            Some(Json::Object(profile)) => profile.clone(),
+            Some(_) => return Err(anyhow!("Profile {name} must be an object")),
+            None => {
+                let known: Vec<_> = profiles.keys().map(String::as_str).collect();
+                return Err(anyhow!(
+                    "Unknown profile {name}, expected one of: {}",
+                    known.join(", ")
+                ));
+            },
+        };
+        next = match profile.remove("extends") {
+            Some(Json::String(parent)) => Some(parent),
+            Some(_) => return Err(anyhow!("extends of profile {name} must be a profile name")),
+            None => None,
+        };
+        lineage.push(Json::Object(profile));
+        seen.push(name);
+    }
+    Ok(lineage)
+}
+
+/// Merges `overrides` into `base`, objects key by key and any other value as
+/// a whole
+fn merge(base: &mut Json, overrides: Json) {
+    match (base, overrides) {
+        (Json::Object(base), Json::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge(base.entry(key).or_insert(Json::Null), value);
+            }
+        },
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(unix)]
+fn world_readable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o004 != 0)
+}
+
+#[cfg(not(unix))]
+fn world_readable(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn interpolates_variables_and_defaults() {
+        env::set_var("BENCHY_TEST_HOST", "hub.example");
+        env::set_var("BENCHY_TEST_EMPTY", "");
+
+        let interpolated =
+            interpolate("https://${BENCHY_TEST_HOST}:${BENCHY_TEST_UNSET:-8080}/graphql").unwrap();
+        assert_eq!(interpolated, "https://hub.example:8080/graphql");
+        assert_eq!(
+            interpolate("${BENCHY_TEST_HOST:-other}").unwrap(),
+            "hub.example"
+        );
+        assert_eq!(
+            interpolate("${BENCHY_TEST_EMPTY:-fallback}").unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate("${BENCHY_TEST_EMPTY}").unwrap(), "");
+        assert_eq!(interpolate("${BENCHY_TEST_UNSET:-}").unwrap(), "");
+    }
+
+    #[test]
+    fn keeps_escapes_and_lone_dollars() {
+        env::set_var("BENCHY_TEST_ESCAPED", "value");
+
+        assert_eq!(
+            interpolate("$${BENCHY_TEST_ESCAPED} costs $5").unwrap(),
+            "${BENCHY_TEST_ESCAPED} costs $5"
+        );
+    }
+
+    #[test]
+    fn rejects_unset_variables_and_unclosed_placeholders() {
+        let unset = interpolate("${BENCHY_TEST_UNSET}").unwrap_err();
+        assert_eq!(
+            unset.to_string(),
+            "Environment variable BENCHY_TEST_UNSET is not set"
+        );
+        assert!(interpolate("${BENCHY_TEST_UNSET").is_err());
+    }
+
+    #[test]
+    fn types_whole_placeholders() {
+        env::set_var("BENCHY_TEST_COUNT", "12");
+        env::set_var("BENCHY_TEST_FLAG", "true");
+
+        let mut value = json!({
+            "hub": {
+                "token": "${BENCHY_TEST_COUNT}",
+                "timeouts": { "query": { "idle_ms": "${BENCHY_TEST_COUNT}" } },
+            },
+            "settings": {
+                "iterations": "${BENCHY_TEST_COUNT}",
+                "log_level": "run ${BENCHY_TEST_COUNT}",
+            },
+            "mint": {
+                "compressed": "${BENCHY_TEST_FLAG}",
+                "collection_id": "${BENCHY_TEST_UNSET:-007}",
+            },
+        });
+        expand(&mut value, "").unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "hub": { "token": "12", "timeouts": { "query": { "idle_ms": 12 } } },
+                "settings": { "iterations": 12, "log_level": "run 12" },
+                "mint": { "compressed": true, "collection_id": "007" },
+            })
+        );
+
+        let err = expand(&mut json!({ "hub": { "url": "${BENCHY_TEST_UNSET}" } }), "");
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "Unable to interpolate hub.url"
+        );
+    }
+
+    /// Writes `files` to a fresh directory, returning its path
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("benchy-{name}-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn merges_includes_under_the_including_file() {
+        let dir = fixture("include", &[
+            (
+                "config.yaml",
+                "include: [base.toml]\nsettings:\n  iterations: 5\n",
+            ),
+            ("base.toml", "[settings]\niterations = 1\nparallelism = 2\n"),
+        ]);
+
+        let value = read(&dir.join("config.yaml"), &mut Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "settings": { "iterations": 5, "parallelism": 2 } })
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn detects_include_cycles() {
+        let dir = fixture("cycle", &[
+            ("a.json", r#"{ "include": "b.yaml" }"#),
+            ("b.yaml", "include: [a.json]\n"),
+        ]);
+
+        let err = read(&dir.join("a.json"), &mut Vec::new(), &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().ends_with("a.json includes itself"), "{err}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn finds_literal_secrets_in_raw_values() {
+        let value = json!({
+            "hub": { "token": "${HUB_TOKEN}", "proxy": { "password": "hunter2" } },
+            "push": { "token": "${PUSH_TOKEN:-fallback}" },
+            "profiles": { "ci": { "hub": { "token": "env:CI_TOKEN" } } },
+        });
+
+        assert_eq!(literals(&value), ["hub.proxy.password", "push.token"]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use opentelemetry::Context;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::MintConfig,
+    graphql::*,
+    hub::Throttling,
+    mint::process_response,
+    report::{EventKind, Record},
+    HubClient,
+};
+
+/// Credits charged by the Hub for the configured mint, from its `creditSheet`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Pricing {
+    pub mint: u64,
+    pub retry: Option<u64>,
+}
+
+impl Pricing {
+    /// # Errors
+    ///
+    /// Will return `Err` if the credit sheet can't be fetched or has no price
+    /// for the mint action on the configured blockchain
+    pub async fn fetch(hub: &HubClient, mc: &MintConfig) -> Result<Self> {
+        let query = CreditSheet::build_query(credit_sheet::Variables);
+        let res_plain = hub
+            .post(
+                "credit_sheet",
+                &query,
+                &Context::new(),
+                &mut Throttling::default(),
+            )
+            .await?;
+        let sheet = process_response(&res_plain, "credit_sheet", |data: CreditSheetResponse| {
+            Ok(data.credit_sheet)
+        })?;
+
+        let blockchain = mc.blockchain.as_deref().unwrap_or("SOLANA");
+        let action = if mc.compressed {
+            "MINT_COMPRESSED"
+        } else {
+            "MINT"
+        };
+        let price = |action: &str| {
+            sheet
+                .iter()
+                .filter(|a| a.action.eq_ignore_ascii_case(action))
+                .flat_map(|a| &a.blockchains)
+                .find(|b| b.blockchain.eq_ignore_ascii_case(blockchain))
+                .and_then(|b| b.credits)
+        };
+
+        Ok(Self {
+            mint: price(action)
+                .ok_or_else(|| anyhow!("No {action} price on {blockchain} in the credit sheet"))?,
+            retry: price("RETRY_MINT"),
+        })
+    }
+}
+
+/// Credit balance of the organization
+///
+/// # Errors
+///
+/// Will return `Err` if the organization or its credits can't be fetched
+pub async fn balance(hub: &HubClient, organization: Uuid) -> Result<i64> {
+    let query =
+        OrganizationCredits::build_query(organization_credits::Variables { id: organization });
+    let res_plain = hub
+        .post(
+            "organization_credits",
+            &query,
+            &Context::new(),
+            &mut Throttling::default(),
+        )
+        .await?;
+    process_response(
+        &res_plain,
+        "organization_credits",
+        |data: OrganizationCreditsResponse| {
+            data.organization
+                .and_then(|o| o.credits)
+                .map(|c| c.balance)
+                .ok_or_else(|| anyhow!("No credits found for organization {organization}"))
+        },
+    )
+}
+
+/// Balance of the organization if one is configured, failures are only
+/// logged
+pub async fn snapshot(hub: &HubClient, organization: Option<Uuid>) -> Option<i64> {
+    let organization = organization?;
+    balance(hub, organization)
+        .await
+        .map_err(|e| warn!("Unable to fetch the credit balance of {organization}: {e}"))
+        .ok()
+}
+
+/// Credits expected from the credit sheet and actually consumed by the run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditUsage {
+    pub mint_cost: u64,
+    pub retry_cost: Option<u64>,
+    /// Submitted mints and retries at the credit sheet prices
+    pub expected: u64,
+    pub balance_before: Option<i64>,
+    pub balance_after: Option<i64>,
+    /// Drop of the organization balance during the run, which includes any
+    /// other usage of the organization meanwhile
+    pub actual: Option<i64>,
+    /// Actual credits, or else expected, per created mint
+    pub per_created_mint: Option<f64>,
+}
+
+impl CreditUsage {
+    pub fn new(
+        pricing: Pricing,
+        records: &[Record],
+        balance_before: Option<i64>,
+        balance_after: Option<i64>,
+    ) -> Self {
+        let retries: u64 = records.iter().map(|r| r.retry_count).sum();
+        let submitted = records
+            .iter()
+            .filter(|r| r.outcome() != EventKind::Rejected)
+            .count() as u64;
+        let expected = submitted * pricing.mint + retries * pricing.retry.unwrap_or_default();
+        let actual = balance_before.zip(balance_after).map(|(b, a)| b - a);
+        let created = records.iter().filter(|r| r.success).count();
+
+        Self {
+            mint_cost: pricing.mint,
+            retry_cost: pricing.retry,
+            expected,
+            balance_before,
+            balance_after,
+            actual,
+            per_created_mint: (created > 0)
+                .then(|| actual.unwrap_or(expected as i64) as f64 / created as f64),
+        }
+    }
+}
+
+impl fmt::Display for CreditUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} credits expected", self.expected)?;
+        if let Some(actual) = self.actual {
+            write!(f, ", {actual} consumed")?;
+        }
+        if let Some(per_mint) = self.per_created_mint {
+            write!(f, ", {per_mint:.2} per created mint")?;
+        }
+        Ok(())
+    }
+}
+
+/// Worst case cost of the run before it starts
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub mints: u64,
+    /// Retries allowed by `max_retries`, `None` when retries are unbounded
+    pub retries: Option<u64>,
+    /// Credits of the mints alone
+    pub minimum: u64,
+    /// Credits of the mints and every allowed retry, `None` when retries are
+    /// unbounded and charged
+    pub credits: Option<u64>,
+}
+
+impl Estimate {
+    pub fn new(pricing: Pricing, mints: u64, max_retries: Option<u64>) -> Self {
+        let retries = max_retries.map(|max| max * mints);
+        let minimum = mints * pricing.mint;
+        let retry = pricing.retry.unwrap_or_default();
+        let credits = match retries {
+            Some(retries) => Some(minimum + retries * retry),
+            None => (retry == 0).then_some(minimum),
+        };
+        Self {
+            mints,
+            retries,
+            minimum,
+            credits,
+        }
+    }
+
+    /// Whether the run may spend more than `cap` credits
+    pub fn exceeds(&self, cap: u64) -> bool {
+        self.credits.map(|credits| credits > cap).unwrap_or(true)
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.retries, self.credits) {
+            (Some(0), _) => write!(f, "{} mints, {} credits", self.mints, self.minimum),
+            (Some(retries), Some(credits)) => write!(
+                f,
+                "{} mutations ({} mints, up to {retries} retries), up to {credits} credits",
+                self.mints + retries,
+                self.mints,
+            ),
+            (_, Some(credits)) => write!(
+                f,
+                "{} mints and unbounded free retries, {credits} credits",
+                self.mints
+            ),
+            (_, None) => write!(
+                f,
+                "{} mints and unbounded retries, at least {} credits",
+                self.mints, self.minimum
+            ),
+        }
+    }
+}
+
+/// Credits the run may spend, mints and retries are charged before being
+/// sent and refused once the cap would be exceeded
+#[derive(Debug)]
+pub struct Budget {
+    pricing: Pricing,
+    cap: u64,
+    spent: AtomicU64,
+}
+
+impl Budget {
+    pub fn new(pricing: Pricing, cap: u64) -> Self {
+        Self {
+            pricing,
+            cap,
+            spent: AtomicU64::new(0),
+        }
+    }
+
+    pub fn cap(&self) -> u64 {
+        self.cap
+    }
+
+    /// Charges `count` mints, or nothing if they don't fit in the budget
+    pub fn mints(&self, count: u64) -> bool {
+        self.charge(count * self.pricing.mint)
+    }
+
+    /// Charges a retry, or nothing if it doesn't fit in the budget
+    pub fn retry(&self) -> bool {
+        self.charge(self.pricing.retry.unwrap_or_default())
+    }
+
+    /// Gives back the credits of mints the Hub refused
+    pub fn refund(&self, count: u64) {
+        self.spent
+            .fetch_sub(count * self.pricing.mint, Ordering::SeqCst);
+    }
+
+    /// Gives back the credits of a retry the Hub refused
+    pub fn refund_retry(&self) {
+        self.spent
+            .fetch_sub(self.pricing.retry.unwrap_or_default(), Ordering::SeqCst);
+    }
+
+    fn charge(&self, credits: u64) -> bool {
+        self.spent
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spent| {
+                (spent + credits <= self.cap).then_some(spent + credits)
+            })
+            .is_ok()
+    }
+}
@@ -1,11 +1,58 @@
+use chrono::{DateTime, Utc};
 pub use csv::Writer;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-#[derive(Debug, Serialize)]
-pub struct Record {
+
+use crate::report::{ErrorClass, Record};
+
+/// Flat CSV view of a [`Record`], timelines are only available in the JSON formats
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Row {
     pub mint_id: Uuid,
     pub completion_sec: u64,
     pub retry_count: u64,
     pub success: bool,
     pub reason: String,
+    pub submitted_at: DateTime<Utc>,
+    pub completion_ms: u64,
+    pub error_class: Option<ErrorClass>,
+    #[serde(default)]
+    pub throttled: u64,
+    #[serde(default)]
+    pub backoff_ms: u64,
+}
+
+impl From<&Record> for Row {
+    fn from(record: &Record) -> Self {
+        Self {
+            mint_id: record.mint_id,
+            completion_sec: record.completion_sec,
+            retry_count: record.retry_count,
+            success: record.success,
+            reason: record.reason.clone(),
+            submitted_at: record.submitted_at,
+            completion_ms: record.completion_ms,
+            error_class: record.error_class,
+            throttled: record.throttled,
+            backoff_ms: record.backoff_ms,
+        }
+    }
+}
+
+impl From<Row> for Record {
+    fn from(row: Row) -> Self {
+        Self {
+            mint_id: row.mint_id,
+            completion_sec: row.completion_sec,
+            retry_count: row.retry_count,
+            success: row.success,
+            reason: row.reason,
+            submitted_at: row.submitted_at,
+            completion_ms: row.completion_ms,
+            error_class: row.error_class,
+            throttled: row.throttled,
+            backoff_ms: row.backoff_ms,
+            timeline: Vec::new(),
+        }
+    }
 }
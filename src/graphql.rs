@@ -1,3 +1,5 @@
+use std::fmt;
+
 pub use graphql_client::GraphQLQuery;
 pub use mint_status::CreationStatus;
 pub use mint_to_collection::*;
@@ -19,6 +21,18 @@ pub struct GraphQLResponse<T> {
     pub errors: Option<Vec<GraphQLError>>,
 }
 
+/// Messages of a response with GraphQL errors, the Hub refused the operation
+#[derive(Debug)]
+pub struct GraphQLErrors(pub Vec<String>);
+
+impl fmt::Display for GraphQLErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GraphQL Errors: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for GraphQLErrors {}
+
 #[derive(Debug, Deserialize, GraphQLQuery)]
 #[graphql(
     schema_path = "holaplex.graphql",
@@ -43,6 +57,22 @@ pub struct RetryMintToCollection;
 )]
 pub struct MintStatus;
 
+#[derive(Debug, Deserialize, GraphQLQuery)]
+#[graphql(
+    schema_path = "holaplex.graphql",
+    query_path = "queries/credit_sheet.graphql",
+    response_derives = "Debug, Deserialize, Serialize"
+)]
+pub struct CreditSheet;
+
+#[derive(Debug, Deserialize, GraphQLQuery)]
+#[graphql(
+    schema_path = "holaplex.graphql",
+    query_path = "queries/organization_credits.graphql",
+    response_derives = "Debug, Deserialize, Serialize"
+)]
+pub struct OrganizationCredits;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RetryMintToCollectionData {
     #[serde(rename = "collectionMint")]
@@ -85,3 +115,36 @@ pub struct MintData {
     #[serde(rename = "creationStatus")]
     pub creation_status: CreationStatus,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreditSheetResponse {
+    #[serde(rename = "creditSheet")]
+    pub credit_sheet: Vec<ActionCost>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActionCost {
+    pub action: String,
+    pub blockchains: Vec<BlockchainCost>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockchainCost {
+    pub blockchain: String,
+    pub credits: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationCreditsResponse {
+    pub organization: Option<OrganizationData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizationData {
+    pub credits: Option<CreditsData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreditsData {
+    pub balance: i64,
+}
@@ -0,0 +1,235 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    markdown,
+    report::{Metadata, Record, Run},
+    stats::Summary,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id TEXT PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    version TEXT NOT NULL,
+    hub_url TEXT,
+    config_fingerprint TEXT NOT NULL,
+    settings TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    successful INTEGER NOT NULL,
+    success_rate REAL NOT NULL,
+    throughput REAL NOT NULL,
+    p50 REAL,
+    p95 REAL
+);
+CREATE INDEX IF NOT EXISTS runs_started_at ON runs (started_at);
+CREATE INDEX IF NOT EXISTS runs_config_fingerprint ON runs (config_fingerprint);
+CREATE TABLE IF NOT EXISTS records (
+    run_id TEXT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    mint_id TEXT NOT NULL,
+    submitted_at TEXT NOT NULL,
+    completion_ms INTEGER NOT NULL,
+    retry_count INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    error_class TEXT,
+    reason TEXT NOT NULL,
+    record TEXT NOT NULL,
+    PRIMARY KEY (run_id, mint_id)
+);
+";
+
+/// Local SQLite database keeping every run, so results can be compared
+/// across weeks. Summary columns are denormalized on `runs` to keep trend
+/// queries simple, e.g.
+/// `SELECT started_at, p95 FROM runs WHERE config_fingerprint = ?`.
+pub struct History {
+    conn: Connection,
+}
+
+/// One line of `benchy history list`
+pub struct Entry {
+    pub id: String,
+    pub started_at: String,
+    pub version: String,
+    pub config_fingerprint: String,
+    pub total: usize,
+    pub success_rate: f64,
+    pub throughput: f64,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+}
+
+impl History {
+    /// # Errors
+    ///
+    /// Will return `Err` if the database can't be opened or migrated
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn save(&mut self, run: &Run) -> Result<()> {
+        let m = &run.metadata;
+        let s = &run.summary;
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO runs (id, started_at, finished_at, version, hub_url, config_fingerprint, \
+             settings, metadata, summary, total, successful, success_rate, throughput, p50, p95) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                m.run_id.to_string(),
+                m.started_at.to_rfc3339(),
+                m.finished_at.map(|t| t.to_rfc3339()),
+                m.version,
+                m.config["hub"]["url"].as_str(),
+                m.config_fingerprint,
+                serde_json::to_string(&m.settings)?,
+                serde_json::to_string(m)?,
+                serde_json::to_string(s)?,
+                s.total,
+                s.successful,
+                s.success_rate,
+                s.throughput,
+                s.completion.as_ref().map(|p| p.p50),
+                s.completion.as_ref().map(|p| p.p95),
+            ],
+        )?;
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO records (run_id, mint_id, submitted_at, completion_ms, retry_count, \
+                 success, error_class, reason, record) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for r in &run.records {
+                stmt.execute(params![
+                    m.run_id.to_string(),
+                    r.mint_id.to_string(),
+                    r.submitted_at.to_rfc3339(),
+                    r.completion_ms,
+                    r.retry_count,
+                    r.success,
+                    r.error_class.map(|c| c.to_string()),
+                    r.reason,
+                    serde_json::to_string(r)?,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Most recent runs first
+    pub fn list(&self, limit: usize, fingerprint: Option<&str>) -> Result<Vec<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started_at, version, config_fingerprint, total, success_rate, throughput, \
+             p50, p95 FROM runs \
+             WHERE ?1 IS NULL OR substr(config_fingerprint, 1, length(?1)) = ?1 \
+             ORDER BY started_at DESC LIMIT ?2",
+        )?;
+        // Fingerprints are lowercase hex, prefixes match them in any case
+        let fingerprint = fingerprint.map(str::to_lowercase);
+        let rows = stmt.query_map(params![fingerprint, limit], |row| {
+            Ok(Entry {
+                id: row.get(0)?,
+                started_at: row.get(1)?,
+                version: row.get(2)?,
+                config_fingerprint: row.get(3)?,
+                total: row.get(4)?,
+                success_rate: row.get(5)?,
+                throughput: row.get(6)?,
+                p50: row.get(7)?,
+                p95: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Expands a unique run id prefix to the full id
+    pub fn resolve(&self, prefix: &str) -> Result<String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM runs WHERE substr(id, 1, length(?1)) = ?1 LIMIT 2")?;
+        // Ids are stored lowercase, prefixes match them in any case
+        let ids = stmt
+            .query_map([prefix.to_lowercase()], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        match ids.as_slice() {
+            [id] => Ok(id.clone()),
+            [] => Err(anyhow!("No run found with id {prefix}")),
+            _ => Err(anyhow!("Run id {prefix} is ambiguous")),
+        }
+    }
+
+    pub fn load(&self, prefix: &str) -> Result<Run> {
+        let id = self.resolve(prefix)?;
+        let (metadata, summary): (String, String) = self
+            .conn
+            .query_row(
+                "SELECT metadata, summary FROM runs WHERE id = ?1",
+                [&id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("No run found with id {id}"))?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT record FROM records WHERE run_id = ?1 ORDER BY submitted_at")?;
+        let records = stmt
+            .query_map([&id], |row| row.get::<_, String>(0))?
+            .map(|record| Ok(serde_json::from_str::<Record>(&record?)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Run {
+            metadata: serde_json::from_str::<Metadata>(&metadata)?,
+            summary: serde_json::from_str::<Summary>(&summary)?,
+            records,
+        })
+    }
+
+    pub fn delete(&self, prefix: &str) -> Result<String> {
+        let id = self.resolve(prefix)?;
+        self.conn.execute("DELETE FROM runs WHERE id = ?1", [&id])?;
+        Ok(id)
+    }
+}
+
+/// Prints the runs as a plain text table
+pub fn print_list(entries: &[Entry]) {
+    println!(
+        "{:<36}  {:<19}  {:<8}  {:<12}  {:>6}  {:>8}  {:>9}  {:>8}  {:>8}",
+        "RUN", "STARTED", "VERSION", "CONFIG", "MINTS", "SUCCESS", "MINTS/S", "P50", "P95"
+    );
+    for e in entries {
+        println!(
+            "{:<36}  {:<19.19}  {:<8}  {:<12.12}  {:>6}  {:>7.1}%  {:>9.3}  {:>8}  {:>8}",
+            e.id,
+            e.started_at,
+            e.version,
+            e.config_fingerprint,
+            e.total,
+            e.success_rate * 100.0,
+            e.throughput,
+            secs(e.p50),
+            secs(e.p95)
+        );
+    }
+}
+
+pub fn print_run(run: &Run) {
+    print!("{}", markdown::render(run));
+}
+
+fn secs(v: Option<f64>) -> String {
+    v.map(|v| format!("{v:.2}s"))
+        .unwrap_or_else(|| "-".to_string())
+}
@@ -0,0 +1,408 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::Result;
+
+use crate::{
+    report::{EventKind, Record, Run},
+    stats,
+};
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 300.0;
+const MARGIN_LEFT: f64 = 55.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 15.0;
+const MARGIN_BOTTOM: f64 = 45.0;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em auto; max-width: 1340px; color: #222; }
+h1 { font-size: 1.5em; }
+section { display: inline-block; vertical-align: top; margin: 0 1em 1.5em 0; }
+table { border-collapse: collapse; font-size: 0.9em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+th { background: #f3f3f3; }
+#failures th { cursor: pointer; }
+svg text { font-size: 11px; fill: #444; }
+.axis { stroke: #888; }
+.grid { stroke: #eee; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll('#failures th').forEach((th, i) => th.addEventListener('click', () => {
+  const body = th.closest('table').tBodies[0];
+  const asc = th.dataset.asc !== 'true';
+  th.dataset.asc = asc;
+  Array.from(body.rows).sort((a, b) => {
+    const x = a.cells[i].textContent, y = b.cells[i].textContent;
+    // Only whole numbers sort numerically, mint ids and dates start with digits
+    const num = s => s.trim() === '' ? NaN : Number(s);
+    const nx = num(x), ny = num(y);
+    const c = isNaN(nx) || isNaN(ny) ? x.localeCompare(y) : nx - ny;
+    return asc ? c : -c;
+  }).forEach(r => body.appendChild(r));
+}));
+"#;
+
+/// Renders the run as a single HTML file with inline SVG charts, no network
+/// access is needed to open it.
+///
+/// # Errors
+///
+/// Will return `Err` if the file can't be written
+pub fn save(path: &Path, run: &Run) -> Result<()> {
+    fs::write(path, render(run))?;
+    Ok(())
+}
+
+fn render(run: &Run) -> String {
+    let m = &run.metadata;
+    let s = &run.summary;
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Benchy run {}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>Benchy run {}</h1>\n",
+        m.run_id, m.run_id
+    );
+
+    let mut overview = vec![
+        ("Benchy version", m.version.clone()),
+        ("Started", m.started_at.to_rfc3339()),
+        (
+            "Finished",
+            m.finished_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ),
+        ("Iterations", opt(m.settings.iterations)),
+        ("Parallelism", opt(m.settings.parallelism)),
+        ("Retry", opt(m.settings.retry)),
+        ("Mints", s.total.to_string()),
+        ("Successful", s.successful.to_string()),
+        ("Failed", s.failed.to_string()),
+        ("Retries", s.retries.to_string()),
+        ("Success rate", format!("{:.1}%", s.success_rate * 100.0)),
+        ("Duration", format!("{:.1}s", s.duration_sec)),
+        ("Throughput", format!("{:.3} mints/s", s.throughput)),
+    ];
+    if let Some(p) = &s.completion {
+        overview.extend([
+            ("Completion p50", format!("{:.2}s", p.p50)),
+            ("Completion p95", format!("{:.2}s", p.p95)),
+            ("Completion p99", format!("{:.2}s", p.p99)),
+            ("Completion max", format!("{:.2}s", p.max)),
+        ]);
+    }
+    html.push_str("<section>\n<h2>Summary</h2>\n<table>\n");
+    for (name, value) in overview {
+        let _ = writeln!(html, "<tr><th>{name}</th><td>{}</td></tr>", escape(&value));
+    }
+    html.push_str("</table>\n</section>\n");
+
+    chart(&mut html, "Status breakdown", &status_pie(&run.records));
+    chart(
+        &mut html,
+        "Completion time histogram",
+        &histogram(&run.records),
+    );
+    chart(
+        &mut html,
+        "Completion time by submission time",
+        &scatter(run),
+    );
+    chart(&mut html, "Throughput over time", &throughput(run));
+
+    failures(&mut html, &run.records);
+
+    let _ = write!(html, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+fn chart(html: &mut String, title: &str, svg: &str) {
+    let _ = writeln!(html, "<section>\n<h2>{title}</h2>\n{svg}\n</section>");
+}
+
+fn failures(html: &mut String, records: &[Record]) {
+    let failed: Vec<_> = records.iter().filter(|r| !r.success).collect();
+    let _ = writeln!(html, "<h2>Failures ({})</h2>", failed.len());
+    if failed.is_empty() {
+        html.push_str("<p>No failed mints.</p>\n");
+        return;
+    }
+
+    html.push_str("<table id=\"failures\">\n<thead><tr><th>Mint</th><th>Submitted</th><th>Completion (s)</th><th>Retries</th><th>Outcome</th><th>Reason</th></tr></thead>\n<tbody>\n");
+    for r in failed {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            r.mint_id,
+            r.submitted_at.to_rfc3339(),
+            r.completion_secs(),
+            r.retry_count,
+            r.outcome(),
+            escape(&r.reason)
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn status_pie(records: &[Record]) -> String {
+    let counts = stats::outcomes(records);
+
+    let (cx, cy, radius) = (150.0, HEIGHT / 2.0, 120.0);
+    let mut svg = svg_open();
+    if records.is_empty() {
+        svg.push_str(&no_data());
+        return svg + "</svg>";
+    }
+
+    let total = records.len() as f64;
+    let mut angle = -std::f64::consts::FRAC_PI_2;
+    for (i, (outcome, count)) in counts.iter().enumerate() {
+        let color = status_color(*outcome);
+        let share = *count as f64 / total;
+        if counts.len() == 1 {
+            let _ = write!(
+                svg,
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{radius}\" fill=\"{color}\"/>"
+            );
+        } else {
+            let end = angle + share * std::f64::consts::TAU;
+            let large = i32::from(share > 0.5);
+            let _ = write!(
+                svg,
+                "<path d=\"M{cx},{cy} L{:.2},{:.2} A{radius},{radius} 0 {large} 1 {:.2},{:.2} Z\" fill=\"{color}\"/>",
+                cx + radius * angle.cos(),
+                cy + radius * angle.sin(),
+                cx + radius * end.cos(),
+                cy + radius * end.sin()
+            );
+            angle = end;
+        }
+
+        let y = 40.0 + i as f64 * 22.0;
+        let _ = write!(
+            svg,
+            "<rect x=\"320\" y=\"{}\" width=\"14\" height=\"14\" fill=\"{color}\"/><text x=\"340\" y=\"{y}\">{outcome}: {count} ({:.1}%)</text>",
+            y - 11.0,
+            share * 100.0
+        );
+    }
+    svg + "</svg>"
+}
+
+fn histogram(records: &[Record]) -> String {
+    let samples: Vec<f64> = records
+        .iter()
+        .filter(|r| r.success)
+        .map(Record::completion_secs)
+        .collect();
+    let mut svg = svg_open();
+    if samples.is_empty() {
+        svg.push_str(&no_data());
+        return svg + "</svg>";
+    }
+
+    let max = samples.iter().copied().fold(0.0, f64::max).max(1.0);
+    let bins = (samples.len() as f64).sqrt().ceil().clamp(5.0, 30.0) as usize;
+    let width = max / bins as f64;
+    let mut counts = vec![0usize; bins];
+    for v in &samples {
+        counts[((v / width) as usize).min(bins - 1)] += 1;
+    }
+
+    let plot = Plot::new(max, *counts.iter().max().unwrap_or(&1) as f64);
+    svg.push_str(&plot.axes("completion time (s)", "mints"));
+    for (i, count) in counts.iter().enumerate() {
+        let x0 = plot.x(i as f64 * width);
+        let x1 = plot.x((i + 1) as f64 * width);
+        let y = plot.y(*count as f64);
+        let _ = write!(
+            svg,
+            "<rect x=\"{x0:.2}\" y=\"{y:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"#4c78a8\" stroke=\"#fff\"><title>{count}</title></rect>",
+            (x1 - x0).max(1.0),
+            plot.y(0.0) - y
+        );
+    }
+    svg + "</svg>"
+}
+
+fn scatter(run: &Run) -> String {
+    let start = run.metadata.started_at;
+    let points: Vec<(f64, f64, &Record)> = run
+        .records
+        .iter()
+        .map(|r| {
+            let offset = (r.submitted_at - start).num_milliseconds().max(0) as f64 / 1000.0;
+            (offset, r.completion_secs(), r)
+        })
+        .collect();
+    let mut svg = svg_open();
+    if points.is_empty() {
+        svg.push_str(&no_data());
+        return svg + "</svg>";
+    }
+
+    let x_max = points.iter().map(|p| p.0).fold(0.0, f64::max).max(1.0);
+    let y_max = points.iter().map(|p| p.1).fold(0.0, f64::max).max(1.0);
+    let plot = Plot::new(x_max, y_max);
+    svg.push_str(&plot.axes("submitted after (s)", "completion time (s)"));
+    for (x, y, r) in points {
+        let _ = write!(
+            svg,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"3\" fill=\"{}\" fill-opacity=\"0.7\"><title>{} {:.2}s</title></circle>",
+            plot.x(x),
+            plot.y(y),
+            status_color(r.outcome()),
+            r.mint_id,
+            y
+        );
+    }
+    svg + "</svg>"
+}
+
+fn throughput(run: &Run) -> String {
+    let start = run.metadata.started_at;
+    let completions: Vec<f64> = run
+        .records
+        .iter()
+        .filter(|r| r.success)
+        .map(|r| {
+            (r.submitted_at - start).num_milliseconds().max(0) as f64 / 1000.0 + r.completion_secs()
+        })
+        .collect();
+    let mut svg = svg_open();
+    if completions.is_empty() {
+        svg.push_str(&no_data());
+        return svg + "</svg>";
+    }
+
+    let duration = run
+        .summary
+        .duration_sec
+        .max(completions.iter().copied().fold(0.0, f64::max))
+        .max(1.0);
+    let width = (duration / 30.0).max(1.0);
+    let buckets = (duration / width).ceil() as usize;
+    let mut counts = vec![0usize; buckets];
+    for t in completions {
+        counts[((t / width) as usize).min(buckets - 1)] += 1;
+    }
+    let rates: Vec<f64> = counts.iter().map(|c| *c as f64 / width).collect();
+
+    let plot = Plot::new(duration, rates.iter().copied().fold(0.0, f64::max));
+    svg.push_str(&plot.axes("time since start (s)", "created mints/s"));
+    let line: Vec<String> = rates
+        .iter()
+        .enumerate()
+        .map(|(i, rate)| {
+            format!(
+                "{:.2},{:.2}",
+                plot.x((i as f64 + 0.5) * width),
+                plot.y(*rate)
+            )
+        })
+        .collect();
+    let _ = write!(
+        svg,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"#4c78a8\" stroke-width=\"2\"/>",
+        line.join(" ")
+    );
+    svg + "</svg>"
+}
+
+struct Plot {
+    x_max: f64,
+    y_max: f64,
+}
+
+impl Plot {
+    fn new(x_max: f64, y_max: f64) -> Self {
+        Self {
+            x_max: if x_max > 0.0 { x_max } else { 1.0 },
+            y_max: if y_max > 0.0 { y_max } else { 1.0 },
+        }
+    }
+
+    fn x(&self, v: f64) -> f64 {
+        MARGIN_LEFT + v / self.x_max * (WIDTH - MARGIN_LEFT - MARGIN_RIGHT)
+    }
+
+    fn y(&self, v: f64) -> f64 {
+        HEIGHT - MARGIN_BOTTOM - v / self.y_max * (HEIGHT - MARGIN_TOP - MARGIN_BOTTOM)
+    }
+
+    fn axes(&self, x_label: &str, y_label: &str) -> String {
+        let (left, bottom) = (self.x(0.0), self.y(0.0));
+        let (right, top) = (self.x(self.x_max), self.y(self.y_max));
+        let mut svg = String::new();
+
+        for i in 0..=5 {
+            let fraction = f64::from(i) / 5.0;
+            let (xv, yv) = (self.x_max * fraction, self.y_max * fraction);
+            let (x, y) = (self.x(xv), self.y(yv));
+            let _ = write!(
+                svg,
+                "<line class=\"grid\" x1=\"{left}\" y1=\"{y:.2}\" x2=\"{right}\" y2=\"{y:.2}\"/>\
+                 <text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\">{}</text>\
+                 <text x=\"{x:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{}</text>",
+                left - 6.0,
+                y + 4.0,
+                tick(yv),
+                bottom + 16.0,
+                tick(xv)
+            );
+        }
+        let _ = write!(
+            svg,
+            "<line class=\"axis\" x1=\"{left}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\"/>\
+             <line class=\"axis\" x1=\"{left}\" y1=\"{bottom}\" x2=\"{left}\" y2=\"{top}\"/>\
+             <text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\">{x_label}</text>\
+             <text transform=\"translate(14,{:.2}) rotate(-90)\" text-anchor=\"middle\">{y_label}</text>",
+            (left + right) / 2.0,
+            HEIGHT - 8.0,
+            (top + bottom) / 2.0
+        );
+        svg
+    }
+}
+
+fn svg_open() -> String {
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\" viewBox=\"0 0 {WIDTH} {HEIGHT}\">")
+}
+
+fn no_data() -> String {
+    format!(
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">no data</text>",
+        WIDTH / 2.0,
+        HEIGHT / 2.0
+    )
+}
+
+fn status_color(outcome: EventKind) -> &'static str {
+    match outcome {
+        EventKind::Created => "#54a24b",
+        EventKind::TimedOut => "#f58518",
+        EventKind::Error => "#b279a2",
+        EventKind::Aborted => "#9d9d9d",
+        _ => "#e45756",
+    }
+}
+
+fn tick(v: f64) -> String {
+    if v >= 10.0 || v.fract() == 0.0 {
+        format!("{v:.0}")
+    } else {
+        format!("{v:.1}")
+    }
+}
+
+fn opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -1,28 +1,418 @@
-use anyhow::Result;
-use reqwest::{header, Client};
+use std::{fmt, fs, path::Path, time::Duration};
 
-use crate::config::Hub;
+use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, error, warn};
+use opentelemetry::{trace::TraceContextExt, Context, KeyValue};
+use rand::Rng;
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Certificate, Client, Identity, NoProxy, StatusCode,
+};
+use serde::Serialize;
+
+use crate::{
+    auth::Credentials,
+    config::{self, Backoff, HttpVersion, Hub, RequestTimeouts, Tls},
+    graphql::GraphQLErrors,
+    metrics::METRICS,
+    otel,
+    report::ErrorClass,
+};
+
+/// Characters of the response body kept in a [`HubError`]
+const BODY_SNIPPET: usize = 512;
+
+/// Operations sent with the mutations client, every other one is a query
+const MUTATIONS: &[&str] = &["mint", "retry"];
 
 #[derive(Clone)]
 pub struct HubClient {
-    pub client: Client,
+    /// Client of the mint and retry mutations
+    pub mutations: Client,
+    /// Client of the status and other queries
+    pub queries: Client,
     pub url: String,
+    pub credentials: Credentials,
+    pub backoff: Backoff,
+}
+
+/// Throttled requests of a mint and the time spent backing off
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Throttling {
+    pub count: u64,
+    pub backoff: Duration,
 }
 
 impl HubClient {
+    /// # Errors
+    ///
+    /// Will return `Err` if the token, a header or a certificate is invalid,
+    /// or the headers set `Authorization`
     pub fn new(config: &Hub) -> Result<Self> {
-        let mut headers = header::HeaderMap::new();
-        let header_value = header::HeaderValue::from_str(&config.token.clone())
-            .map_err(|_| "Invalid header value")
-            .unwrap();
-        headers.insert("Authorization", header_value);
-        let hub_client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name {name}"))?;
+            if name == header::AUTHORIZATION {
+                bail!("hub.headers can't set Authorization, set hub.token or hub.auth instead");
+            }
+            let value = HeaderValue::from_str(&value.resolve()?)
+                .with_context(|| format!("Invalid value of header {name}"))?;
+            headers.insert(name, value);
+        }
+        let transport = config.transport.resolved();
+        let (roots, identity) = certificates(&config.tls)?;
+        let proxy = config.proxy.as_ref().map(proxy).transpose()?;
+        if config.tls.dangerous_accept_invalid_certs {
+            warn!("The Hub certificate is not verified, only use this with a local stand-in");
+        }
+        let client = |timeouts: &RequestTimeouts, request_ms: u64| {
+            // Other dependencies enable the native TLS backend of reqwest
+            let mut builder = Client::builder()
+                .use_rustls_tls()
+                .danger_accept_invalid_certs(config.tls.dangerous_accept_invalid_certs);
+            for root in &roots {
+                builder = builder.add_root_certificate(root.clone());
+            }
+            if let Some(identity) = &identity {
+                builder = builder.identity(identity.clone());
+            }
+            if let Some(proxy) = &proxy {
+                builder = builder.proxy(proxy.clone());
+            }
+            builder = match transport.http_version.unwrap_or_default() {
+                HttpVersion::Auto => builder,
+                HttpVersion::Http1 => builder.http1_only(),
+                HttpVersion::Http2 => builder.http2_prior_knowledge(),
+            };
+            if transport.fresh_connections.unwrap_or_default() {
+                builder = builder.pool_max_idle_per_host(0);
+            } else if let Some(max) = transport.pool_max_idle {
+                builder = builder.pool_max_idle_per_host(max);
+            }
+            builder
+                .tcp_nodelay(transport.tcp_nodelay.unwrap_or(true))
+                .tcp_keepalive(transport.tcp_keepalive_ms.map(Duration::from_millis))
+                .default_headers(headers.clone())
+                .connect_timeout(Duration::from_millis(timeouts.connect_ms.unwrap_or(10_000)))
+                .timeout(Duration::from_millis(
+                    timeouts.request_ms.unwrap_or(request_ms),
+                ))
+                .pool_idle_timeout(Duration::from_millis(timeouts.idle_ms.unwrap_or(90_000)))
+                .build()
+        };
 
         Ok(Self {
-            client: hub_client,
+            mutations: client(&config.timeouts.mutation, 60_000)?,
+            queries: client(&config.timeouts.query, 30_000)?,
             url: config.url.to_string(),
+            credentials: Credentials::new(config)?,
+            backoff: config.backoff.clone(),
         })
     }
+
+    /// Sends a GraphQL operation, propagating the trace of `cx` to the Hub
+    /// and recording the HTTP attributes on its span. Requests throttled with
+    /// HTTP 429 or 503 are sent again after the `Retry-After` delay, or else
+    /// an exponential backoff, which is added to `throttling`.
+    ///
+    /// # Errors
+    ///
+    /// Will return a [`HubError`] if the request fails, the Hub answers with
+    /// a non-success HTTP status or it is still throttled after the last
+    /// attempt
+    pub async fn post<T: Serialize + ?Sized>(
+        &self,
+        operation: &'static str,
+        body: &T,
+        cx: &Context,
+        throttling: &mut Throttling,
+    ) -> Result<String> {
+        let span = cx.span();
+        span.set_attribute(KeyValue::new("http.request.method", "POST"));
+        span.set_attribute(KeyValue::new("url.full", self.url.clone()));
+
+        let max_attempts = self.backoff.max_attempts.unwrap_or(5).max(1);
+        let max_wait = Duration::from_millis(self.backoff.max_ms.unwrap_or(30_000));
+        let mut attempt = 1;
+        loop {
+            match self.send(operation, body, cx).await {
+                Ok(text) => return Ok(text),
+                Err(e) if e.resendable(operation) && attempt < max_attempts => {
+                    let wait = e
+                        .retry_after
+                        .map_or_else(|| delay(&self.backoff, attempt), |d| d.min(max_wait));
+                    let backoff_ms = wait.as_millis() as u64;
+                    warn!(
+                        operation,
+                        status:? = e.status.map(|s| s.as_u16()),
+                        attempt,
+                        backoff_ms;
+                        "{operation} request throttled, sending it again in {:.1}s",
+                        wait.as_secs_f64()
+                    );
+                    METRICS.throttle(operation, wait);
+                    throttling.count += 1;
+                    throttling.backoff += wait;
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                },
+                Err(e) => return Err(failed(operation, e)),
+            }
+        }
+    }
+
+    async fn send<T: Serialize + ?Sized>(
+        &self,
+        operation: &'static str,
+        body: &T,
+        cx: &Context,
+    ) -> Result<String, HubError> {
+        let span = cx.span();
+        let client = if MUTATIONS.contains(&operation) {
+            &self.mutations
+        } else {
+            &self.queries
+        };
+        let authorization = self.credentials.header(&self.queries).await?;
+        let res = client
+            .post(self.url.clone())
+            .header(header::AUTHORIZATION, authorization)
+            .headers(otel::headers(cx))
+            .json(body)
+            .send()
+            .await;
+        let res = res.map_err(HubError::transport)?;
+        let status = res.status();
+        span.set_attribute(KeyValue::new(
+            "http.response.status_code",
+            i64::from(status.as_u16()),
+        ));
+        METRICS.response(operation, status.as_u16());
+
+        let headers = res.headers().clone();
+        let text = res.text().await.map_err(HubError::transport)?;
+        if status.is_success() {
+            return Ok(text);
+        }
+        if status == StatusCode::UNAUTHORIZED {
+            self.credentials.refused().await;
+        }
+        Err(HubError::status(status, headers, &text))
+    }
+}
+
+/// Extra root certificates and client identity of the TLS connections
+fn certificates(tls: &Tls) -> Result<(Vec<Certificate>, Option<Identity>)> {
+    let read =
+        |path: &Path| fs::read(path).with_context(|| format!("Unable to read {}", path.display()));
+    let roots = tls
+        .ca_certs
+        .iter()
+        .map(|path| {
+            Certificate::from_pem(&read(path)?)
+                .with_context(|| format!("Invalid CA certificate {}", path.display()))
+        })
+        .collect::<Result<_>>()?;
+
+    let identity = match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), key) => {
+            let mut pem = read(cert)?;
+            if let Some(key) = key {
+                pem.push(b'\n');
+                pem.extend(read(key)?);
+            }
+            Some(
+                Identity::from_pem(&pem)
+                    .with_context(|| format!("Invalid client certificate {}", cert.display()))?,
+            )
+        },
+        (None, Some(_)) => bail!("hub.tls.client_key needs a client_cert"),
+        (None, None) => None,
+    };
+    Ok((roots, identity))
+}
+
+fn proxy(config: &config::Proxy) -> Result<reqwest::Proxy> {
+    let mut proxy = reqwest::Proxy::all(config.url.clone())
+        .with_context(|| format!("Invalid proxy {}", config.url))?;
+    if let Some(username) = &config.username {
+        let password = match &config.password {
+            Some(password) => password
+                .resolve()
+                .context("Unable to read hub.proxy.password")?,
+            None => String::new(),
+        };
+        proxy = proxy.basic_auth(username, &password);
+    }
+    Ok(proxy.no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string)))
+}
+
+/// Exponential delay before the next `attempt` of a throttled request, with
+/// part of it randomized so throttled requests don't come back together
+fn delay(backoff: &Backoff, attempt: u32) -> Duration {
+    let initial = backoff.initial_ms.unwrap_or(500) as f64;
+    let max = backoff.max_ms.unwrap_or(30_000) as f64;
+    let jitter = backoff.jitter.unwrap_or(0.5).clamp(0.0, 1.0);
+    let exp = (initial * 2_f64.powi(attempt as i32 - 1)).min(max);
+    let ms = exp * (1.0 - jitter * rand::thread_rng().gen::<f64>());
+    Duration::from_millis(ms as u64)
+}
+
+/// `Retry-After` header as seconds or an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Counts and logs a failed request
+fn failed(operation: &'static str, e: HubError) -> anyhow::Error {
+    METRICS.request_error(operation, e.class);
+    error!(
+        operation,
+        class:% = e.class,
+        status:? = e.status.map(|s| s.as_u16()),
+        error:% = e;
+        "{operation} request failed: {e}"
+    );
+    if !e.headers.is_empty() {
+        debug!(operation; "{operation} response headers: {:?}", e.headers);
+    }
+    e.into()
+}
+
+/// Failed Hub request, with what was received of the HTTP response
+#[derive(Debug)]
+pub struct HubError {
+    pub class: ErrorClass,
+    pub status: Option<StatusCode>,
+    pub headers: HeaderMap,
+    /// Delay asked by the Hub before sending the request again
+    pub retry_after: Option<Duration>,
+    /// Beginning of the response body
+    pub body: String,
+    source: Option<reqwest::Error>,
+}
+
+impl HubError {
+    pub fn status(status: StatusCode, headers: HeaderMap, body: &str) -> Self {
+        let class = match status.as_u16() {
+            401 | 403 => ErrorClass::Auth,
+            408 => ErrorClass::Timeout,
+            429 => ErrorClass::RateLimited,
+            502..=504 => ErrorClass::Gateway,
+            500..=599 => ErrorClass::ServerError,
+            _ => ErrorClass::ClientError,
+        };
+        Self {
+            class,
+            status: Some(status),
+            retry_after: retry_after(&headers),
+            headers,
+            body: body.chars().take(BODY_SNIPPET).collect(),
+            source: None,
+        }
+    }
+
+    pub fn transport(e: reqwest::Error) -> Self {
+        let class = if e.is_timeout() {
+            ErrorClass::Timeout
+        } else if is_tls(&e) {
+            ErrorClass::Tls
+        } else if e.is_connect() {
+            ErrorClass::Connection
+        } else {
+            ErrorClass::Transport
+        };
+        Self {
+            class,
+            status: e.status(),
+            headers: HeaderMap::new(),
+            retry_after: None,
+            body: String::new(),
+            source: Some(e),
+        }
+    }
+
+    pub fn with_class(mut self, class: ErrorClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// The Hub asks to slow down and the request can be sent again. A 503
+    /// may come after a mutation was applied, so mutations are only sent
+    /// again on 429 to avoid minting and charging twice.
+    fn resendable(&self, operation: &str) -> bool {
+        match self.status {
+            Some(StatusCode::TOO_MANY_REQUESTS) => true,
+            Some(StatusCode::SERVICE_UNAVAILABLE) => !MUTATIONS.contains(&operation),
+            _ => false,
+        }
+    }
+
+    /// Class of a failed Hub request, `None` for any other error
+    pub fn class(e: &anyhow::Error) -> Option<ErrorClass> {
+        e.downcast_ref::<Self>().map(|e| e.class)
+    }
+
+    /// The request never reached the Hub or the Hub refused it, so it was
+    /// not charged. After a timeout, a broken connection or a 5xx the Hub
+    /// may still have applied it.
+    pub fn refused(e: &anyhow::Error) -> bool {
+        match e.downcast_ref::<Self>() {
+            Some(e) => matches!(
+                e.class,
+                ErrorClass::Connection
+                    | ErrorClass::Tls
+                    | ErrorClass::Auth
+                    | ErrorClass::ClientError
+                    | ErrorClass::RateLimited
+            ),
+            None => e.is::<GraphQLErrors>(),
+        }
+    }
+}
+
+impl fmt::Display for HubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.status, &self.source) {
+            (_, Some(source)) => write!(f, "{}: {source}", self.class),
+            (Some(status), None) => write!(f, "{}: HTTP {status}: {}", self.class, self.body),
+            (None, None) => write!(f, "{}", self.class),
+        }
+    }
+}
+
+impl std::error::Error for HubError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The TLS errors of reqwest are connect errors, only told apart by their
+/// sources
+fn is_tls(e: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(e) = source {
+        let msg = e.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|m| msg.contains(m))
+        {
+            return true;
+        }
+        source = e.source();
+    }
+    false
 }
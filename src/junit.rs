@@ -0,0 +1,57 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::Result;
+
+use crate::{html::escape, report::Run, slo::Check};
+
+/// Writes the assertion results as a JUnit XML file with one testcase per
+/// assertion, so CI systems can display them.
+///
+/// # Errors
+///
+/// Will return `Err` if the file can't be written
+pub fn save(path: &Path, run: &Run, checks: &[Check]) -> Result<()> {
+    fs::write(path, render(run, checks))?;
+    Ok(())
+}
+
+fn render(run: &Run, checks: &[Check]) -> String {
+    let failures = checks.iter().filter(|c| !c.passed).count();
+    let time = run.summary.duration_sec;
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"benchy\" tests=\"{}\" failures=\"{failures}\" time=\"{time:.3}\">",
+        checks.len()
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"benchy.assertions\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" time=\"{time:.3}\" timestamp=\"{}\">",
+        checks.len(),
+        run.metadata.started_at.format("%Y-%m-%dT%H:%M:%S")
+    );
+    let _ = writeln!(
+        xml,
+        "    <properties>\n      <property name=\"run_id\" value=\"{}\"/>\n      <property name=\"version\" value=\"{}\"/>\n    </properties>",
+        run.metadata.run_id,
+        escape(&run.metadata.version)
+    );
+    for check in checks {
+        let name = escape(&check.name);
+        let message = escape(&check.message);
+        if check.passed {
+            let _ = writeln!(
+                xml,
+                "    <testcase classname=\"benchy.assertions\" name=\"{name}\" time=\"0\">\n      <system-out>{message}</system-out>\n    </testcase>"
+            );
+        } else {
+            let _ = writeln!(
+                xml,
+                "    <testcase classname=\"benchy.assertions\" name=\"{name}\" time=\"0\">\n      <failure type=\"assertion\" message=\"{message}\">{message}</failure>\n    </testcase>"
+            );
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
@@ -0,0 +1,117 @@
+use std::{io::Write, str::FromStr, sync::RwLock};
+
+use anyhow::anyhow;
+use chrono::{SecondsFormat, Utc};
+use env_logger::{Builder, Env};
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    LevelFilter,
+};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value as Json};
+
+/// Shown in place of secrets
+pub const REDACTED: &str = "<redacted>";
+
+/// Secrets shorter than this are not redacted, they would mangle the logs
+const MIN_SECRET_LEN: usize = 4;
+
+static SECRETS: Lazy<RwLock<Vec<String>>> = Lazy::new(RwLock::default);
+
+/// Redacts `secret` from the logs and error messages from now on
+pub fn redact(secret: &str) {
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().unwrap_or_else(|e| e.into_inner());
+    if !secrets.iter().any(|s| s == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// `message` with the known secrets replaced by [`REDACTED`]
+pub fn redacted(message: impl Into<String>) -> String {
+    let mut message = message.into();
+    for secret in SECRETS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        if message.contains(secret.as_str()) {
+            message = message.replace(secret.as_str(), REDACTED);
+        }
+    }
+    message
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("Unknown log format {s}, expected text or json")),
+        }
+    }
+}
+
+/// Target of the headless status lines, logged whatever the filter
+pub const STATUS_TARGET: &str = "benchy::pbs";
+
+/// Logger filtered by `RUST_LOG`, or else by `level`, except for the
+/// [`STATUS_TARGET`] info events, the only output of a headless run. In the
+/// json format
+/// every event is a single line object with the timestamp, level, target,
+/// message and the structured fields of the event (`mint_id`, `operation`,
+/// `status`, `attempt`, `latency_ms`, `error`). Secrets registered with
+/// [`redact`] are redacted in both formats.
+pub fn builder(level: &str, format: LogFormat) -> Builder {
+    let mut builder = Builder::from_env(Env::default().default_filter_or(level));
+    builder.filter_module(STATUS_TARGET, LevelFilter::Info);
+    match format {
+        LogFormat::Text => builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "[{} {:<5} {}] {}",
+                buf.timestamp(),
+                buf.default_styled_level(record.level()),
+                record.target(),
+                redacted(record.args().to_string())
+            )
+        }),
+        LogFormat::Json => builder.format(|buf, record| {
+            let mut event = Map::new();
+            event.insert(
+                "timestamp".to_string(),
+                Utc::now()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+                    .into(),
+            );
+            event.insert("level".to_string(), record.level().as_str().into());
+            event.insert("target".to_string(), record.target().into());
+            event.insert(
+                "message".to_string(),
+                redacted(record.args().to_string()).into(),
+            );
+            let _ = record.key_values().visit(&mut Fields(&mut event));
+            writeln!(buf, "{}", Json::Object(event))
+        }),
+    };
+    builder
+}
+
+struct Fields<'a>(&'a mut Map<String, Json>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = match serde_json::to_value(value).unwrap_or_else(|e| e.to_string().into()) {
+            Json::String(s) => Json::String(redacted(s)),
+            value => value,
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
@@ -1,116 +1,477 @@
 use std::{
     collections::HashMap,
-    fs::File,
+    io::{self, IsTerminal},
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::Arc,
     time::Instant,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
 use futures::stream::{FuturesUnordered, StreamExt};
 use graphql::CreationStatus;
 use indicatif_log_bridge::LogWrapper;
-use log::{error, info};
+use log::{error, info, warn};
 use structopt::StructOpt;
 use tokio::{sync::Semaphore, time::Duration};
 use uuid::Uuid;
 
 use crate::{
-    cli::Opt,
-    config::{Config, Settings},
-    csv::{Record, Writer},
-    hub::HubClient,
+    cli::{Command, GlobalOptions, Opt},
+    compare::{Comparison, ResultSet},
+    config::{Assertions, Config, Settings},
+    credits::{Budget, Pricing},
+    history::History,
+    hub::{HubClient, HubError},
+    metrics::METRICS,
     mint::State,
     pbs::{MultiProgress, ProgressBar},
+    push::Pusher,
+    report::{ErrorClass, EventKind, Metadata, Record, Run, Writer},
+    stats::Summary,
+    tui::{Abort, DASHBOARD},
 };
 
+mod auth;
 mod cli;
+mod compare;
 mod config;
+mod credits;
 mod csv;
 mod graphql;
+mod history;
+mod html;
 mod hub;
+mod junit;
+mod logger;
+mod markdown;
+mod metrics;
 mod mint;
+mod otel;
 mod pbs;
+mod push;
+mod report;
+mod slo;
+mod stats;
+mod tui;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Opt::from_args();
 
-    Config::load(&cli.global.config)?;
+    let res = match &cli.command {
+        Some(Command::History(cmd)) => history(&cli.global, cmd),
+        Some(Command::Report { run }) => report(&cli.global, run),
+        Some(Command::Compare {
+            baseline,
+            candidate,
+        }) => compare(&cli.global, baseline, candidate),
+        None => bench(&cli).await,
+    };
+    // Errors may quote a response or a command line holding a secret
+    res.map_err(|e| anyhow!(logger::redacted(format!("{e:?}"))))
+}
+
+async fn bench(cli: &Opt) -> Result<()> {
+    Config::load(&cli.global.config, cli.global.profile.as_deref())?;
     let cfg = Config::read();
-    let settings = Settings::merge(cfg.settings.clone(), &cli.clone());
+    let mut settings = Settings::merge(cfg.settings.clone(), cli);
+    if settings.status_interval == Some(0) {
+        return Err(anyhow!("status_interval must be at least 1 second"));
+    }
+    let tui = settings.tui.unwrap_or_default() && pbs::is_terminal();
+    if tui {
+        settings.headless = Some(false);
+    }
+    let headless = *settings.headless.get_or_insert_with(|| !pbs::is_terminal());
     let level = settings.log_level.clone().unwrap();
-    let logger =
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&level)).build();
-    let multi = MultiProgress::new();
-    LogWrapper::new(multi.clone(), logger).try_init().unwrap();
+    let logger = logger::builder(&level, cli.global.log_format).build();
+    let multi = pbs::multi(headless || tui);
+    if tui {
+        tui::Logger::new(logger).try_init()?;
+    } else {
+        let max_level = logger.filter();
+        LogWrapper::new(multi.clone(), logger).try_init().unwrap();
+        // The wrapper only probes the default level, which would drop the
+        // status lines of a quieter log level
+        log::set_max_level(max_level);
+    }
+    if settings.tui.unwrap_or_default() && !tui {
+        warn!("Not running on a terminal, the dashboard is disabled");
+    }
+    settings.tui = Some(tui);
+    cfg.check_secrets();
     let hub = HubClient::new(&cfg.hub)?;
-    let wtr = Writer::from_path(cli.global.output).unwrap();
-    run(hub, &settings, multi, wtr).await
+    let (pricing, budget) = preflight(&hub, &settings, cli.cmd.yes).await?;
+    if let Some(addr) = settings.metrics_addr {
+        metrics::serve(addr)?;
+    }
+    if let Some(endpoint) = &settings.otlp_endpoint {
+        otel::init(endpoint)?;
+    }
+    let metadata = Metadata::new(Uuid::new_v4(), &settings)?;
+    let wtr = Writer::from_path(&cli.global.output, cli.global.format)?;
+    let dashboard = if tui {
+        Some(tui::open(metadata.run_id)?)
+    } else {
+        None
+    };
+    let run = run(
+        hub,
+        &settings,
+        multi,
+        wtr,
+        metadata,
+        pricing,
+        budget.as_ref(),
+    )
+    .await;
+    if let Some(dashboard) = dashboard {
+        tui::close(dashboard).await?;
+    }
+    if settings.otlp_endpoint.is_some() {
+        otel::shutdown().await;
+    }
+    let run = run?;
+    info!("Report saved to {}", cli.global.output.display());
+
+    if !cli.global.no_history {
+        History::open(&cli.global.history)?.save(&run)?;
+        info!(
+            "Run {} saved to {}",
+            run.metadata.run_id,
+            cli.global.history.display()
+        );
+    }
+
+    publish(&cli.global, &run, &cfg.assertions)
+}
+
+fn history(global: &GlobalOptions, cmd: &cli::History) -> Result<()> {
+    init_logger(global);
+    let db = History::open(&global.history)?;
+
+    match cmd {
+        cli::History::List { limit, fingerprint } => {
+            history::print_list(&db.list(*limit, fingerprint.as_deref())?);
+        },
+        cli::History::Show { run } => history::print_run(&db.load(run)?),
+        cli::History::Delete { run } => {
+            let id = db.delete(run)?;
+            info!("Run {id} deleted");
+        },
+    }
+    Ok(())
+}
+
+/// Writes the reports of a run stored in the history
+fn report(global: &GlobalOptions, run: &str) -> Result<()> {
+    init_logger(global);
+    let run = History::open(&global.history)?.load(run)?;
+    // Assertions come from the current config, if there is one
+    let assertions = optional_config(global)?
+        .map(|cfg| cfg.assertions.clone())
+        .unwrap_or_default();
+
+    report::save(&global.output, global.format, &run)?;
+    info!("Report saved to {}", global.output.display());
+    publish(global, &run, &assertions)
+}
+
+fn compare(global: &GlobalOptions, baseline: &str, candidate: &str) -> Result<()> {
+    init_logger(global);
+    let regression = optional_config(global)?
+        .map(|cfg| cfg.regression.clone())
+        .unwrap_or_default();
+    let cmp = Comparison::new(
+        ResultSet::load(baseline, &global.history)?,
+        ResultSet::load(candidate, &global.history)?,
+    );
+    cmp.print(regression.alpha.unwrap_or(compare::DEFAULT_ALPHA));
+
+    let checks = compare::evaluate(&regression, &cmp);
+    for check in &checks {
+        if check.passed {
+            info!("Tolerance {} passed: {}", check.name, check.message);
+        } else {
+            error!("Tolerance {} exceeded: {}", check.name, check.message);
+        }
+    }
+
+    let violations = checks.iter().filter(|c| !c.passed).count();
+    if violations > 0 {
+        return Err(anyhow!(
+            "{violations} of {} regression tolerances exceeded",
+            checks.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Commands working on past runs can go without a config file, but not with
+/// a broken one
+fn optional_config(global: &GlobalOptions) -> Result<Option<&'static Config>> {
+    if !global.config.exists() {
+        warn!("No config at {}, using defaults", global.config.display());
+        return Ok(None);
+    }
+    Config::load(&global.config, global.profile.as_deref())?;
+    Ok(Some(Config::read()))
+}
+
+/// Writes the optional reports and checks the assertions of a finished run
+fn publish(global: &GlobalOptions, run: &Run, assertions: &Assertions) -> Result<()> {
+    if let Some(path) = &global.html {
+        html::save(path, run)?;
+        info!("HTML report saved to {}", path.display());
+    }
+    if let Some(path) = &global.summary_markdown {
+        markdown::save(path, run)?;
+        info!("Markdown summary saved to {}", path.display());
+    }
+
+    let checks = slo::evaluate(assertions, run);
+    for check in &checks {
+        if check.passed {
+            info!("Assertion {} passed: {}", check.name, check.message);
+        } else {
+            error!("Assertion {} failed: {}", check.name, check.message);
+        }
+    }
+    if let Some(path) = &global.junit {
+        junit::save(path, run, &checks)?;
+        info!("JUnit report saved to {}", path.display());
+    }
+
+    let violations = checks.iter().filter(|c| !c.passed).count();
+    if violations > 0 {
+        return Err(anyhow!(
+            "{violations} of {} assertions failed",
+            checks.len()
+        ));
+    }
+    Ok(())
+}
+
+fn init_logger(global: &GlobalOptions) {
+    logger::builder("info", global.log_format).init();
+}
+
+/// Prices the run from the credit sheet and checks its estimated cost against
+/// the budget, which needs a confirmation or `--yes` to be exceeded
+async fn preflight(
+    hub: &HubClient,
+    s: &Settings,
+    yes: bool,
+) -> Result<(Option<Pricing>, Option<Budget>)> {
+    let pricing = match Pricing::fetch(hub, &Config::read().mint).await {
+        Ok(pricing) => pricing,
+        Err(e) if s.budget.is_none() => {
+            warn!("Unable to price the run from the credit sheet: {e}");
+            return Ok((None, None));
+        },
+        Err(e) => return Err(e.context("Unable to check the run against the budget")),
+    };
+    let mints = (s.iterations.unwrap() * s.parallelism.unwrap()) as u64;
+    let max_retries = if s.retry.unwrap_or_default() {
+        s.max_retries
+    } else {
+        Some(0)
+    };
+    let estimate = credits::Estimate::new(pricing, mints, max_retries);
+    info!("Estimated cost: {estimate}");
+
+    let Some(cap) = s.budget else {
+        return Ok((Some(pricing), None));
+    };
+    if estimate.exceeds(cap)
+        && !yes
+        && !confirm(&format!(
+            "The run may exceed the budget of {cap} credits, it stops before. Continue?"
+        ))?
+    {
+        return Err(anyhow!(
+            "Estimated cost of {estimate} may exceed the budget of {cap} credits, pass --yes to \
+             run until the budget is spent or set a lower --max-retries"
+        ));
+    }
+    // Confirming only starts the run, the budget still caps it
+    let budget = Budget::new(pricing, cap);
+    info!(
+        "Budget: {} credits, mints and retries stop before exceeding it",
+        budget.cap()
+    );
+    Ok((Some(pricing), Some(budget)))
+}
+
+/// Asks a yes or no question, answered no when not on a terminal
+fn confirm(question: &str) -> Result<bool> {
+    if !(pbs::is_terminal() && io::stdin().is_terminal()) {
+        return Ok(false);
+    }
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
-async fn run(hub: HubClient, s: &Settings, m: MultiProgress, mut wtr: Writer<File>) -> Result<()> {
+async fn run(
+    hub: HubClient,
+    s: &Settings,
+    m: MultiProgress,
+    mut wtr: Writer,
+    mut metadata: Metadata,
+    pricing: Option<Pricing>,
+    budget: Option<&Budget>,
+) -> Result<Run> {
     let semaphore = Arc::new(Semaphore::new(s.parallelism.unwrap()));
     let retry_delay = s.retry_delay.unwrap_or(10);
     let total_mints = s.iterations.unwrap() * s.parallelism.unwrap();
+    METRICS.window().plan(total_mints);
+
+    let cfg = Config::read();
+    let balance_before = credits::snapshot(&hub, cfg.hub.organization).await;
 
     let pbs = pbs::init(&m, total_mints, s.retry.unwrap_or_default()).await;
+    let status = s
+        .headless
+        .unwrap_or_default()
+        .then(|| pbs::status(Duration::from_secs(s.status_interval.unwrap_or(10))));
 
-    let mints = mint(&hub, s, &semaphore, &pbs["mints"]).await?;
+    let (mints, rejected) = mint(&hub, s, &semaphore, &pbs, &mut wtr, budget).await?;
     pbs["mints"].finish_with_message("All mint requests sent!");
+    // Unsent mints will never settle
+    METRICS.window().plan(mints.len() + rejected.len());
 
     if s.iterations.unwrap() < 2 {
         info!("Waiting {retry_delay} seconds before starting mint status verification");
         tokio::time::sleep(Duration::from_secs(retry_delay)).await;
     };
 
-    let records = verify(&hub, &mints, s, &pbs).await;
+    let mut pusher = cfg
+        .push
+        .clone()
+        .map(|cfg| Pusher::new(cfg, metadata.run_id, metadata.started_at));
+    let records = verify(
+        &hub,
+        mints,
+        rejected,
+        s,
+        &pbs,
+        &mut wtr,
+        &mut pusher,
+        budget,
+    )
+    .await?;
 
     pbs::finalize(&pbs["successful"], &records).await;
+    pbs["live"].finish();
+    if let Some(status) = status {
+        status.abort();
+    }
 
-    save(&mut wtr, &records)?;
+    let finished_at = Utc::now();
+    let duration = (finished_at - metadata.started_at)
+        .to_std()
+        .unwrap_or_default();
+    metadata.finished_at = Some(finished_at);
+    let mut summary = Summary::new(&records, duration);
+    summary.requests = METRICS.requests().clone();
+    if let Some(pricing) = pricing {
+        let balance_after = credits::snapshot(&hub, cfg.hub.organization).await;
+        let usage = credits::CreditUsage::new(pricing, &records, balance_before, balance_after);
+        info!("Credits: {usage}");
+        summary.credits = Some(usage);
+    }
+    let run = Run {
+        summary,
+        metadata,
+        records,
+    };
+    if s.headless.unwrap_or_default() {
+        pbs::log_status();
+    }
+    wtr.finish(&run)?;
+    if let Some(pusher) = &pusher {
+        pusher.finish(&run.summary).await;
+    }
 
-    Ok(())
+    Ok(run)
 }
 
+/// Submits the mints, returning the accepted ones and the records of the
+/// rejected ones, which are settled and written to `wtr` right away
 async fn mint(
     hub: &HubClient,
     s: &Settings,
     semaphore: &Arc<Semaphore>,
-    pb: &ProgressBar,
-) -> Result<HashMap<Uuid, Instant>> {
+    pbs: &HashMap<&'static str, ProgressBar>,
+    wtr: &mut Writer,
+    budget: Option<&Budget>,
+) -> Result<(HashMap<Uuid, State>, Vec<Record>)> {
+    let pb = &pbs["mints"];
     let mut mints = HashMap::new();
+    let mut rejected = Vec::new();
+    DASHBOARD.set_delay(Duration::from_secs(s.delay.unwrap_or(0)));
 
     for _ in 0..s.iterations.unwrap_or(0) {
+        while DASHBOARD.paused() && DASHBOARD.abort() == Abort::None {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        if DASHBOARD.abort() != Abort::None {
+            info!("Mint submission stopped");
+            break;
+        }
+        let batch = s.parallelism.unwrap_or(0) as u64;
+        if budget.is_some_and(|b| !b.mints(batch)) {
+            warn!("Mint submission stopped, the next {batch} mints would exceed the budget");
+            break;
+        }
+
         let results: Vec<_> = (0..s.parallelism.unwrap_or(0))
             .map(|_| {
                 let semaphore_clone = semaphore.clone();
                 let hub = hub.clone();
                 async move {
                     let _guard = semaphore_clone.acquire_owned().await;
-                    let start_time = Instant::now();
-                    let result = mint::execute(&hub).await;
+                    let mut state = State::new(Instant::now(), Utc::now());
+                    let result = mint::execute(&hub, &mut state).await;
                     pb.inc(1);
-                    (result, start_time)
+                    (result, state)
                 }
             })
             .collect::<FuturesUnordered<_>>()
             .collect()
             .await;
 
-        for (mint_result, start_time) in results {
-            if let Ok(mint) = mint_result {
-                let mint_id = Uuid::from_str(&mint.id).unwrap();
-                mints.insert(mint_id, start_time);
+        for (mint_result, mut state) in results {
+            match mint_result {
+                Ok(mint) => {
+                    let mint_id = Uuid::from_str(&mint.id).unwrap();
+                    METRICS.submit();
+                    mints.insert(mint_id, state);
+                },
+                Err(e) => {
+                    if let Some(budget) = budget.filter(|_| HubError::refused(&e)) {
+                        budget.refund(1);
+                    }
+                    // The Hub gave the mint no id, record it under a random one
+                    let msg = format!("Failed to submit mint: {e}");
+                    METRICS.reject();
+                    state.event(EventKind::Rejected, Some(msg.clone()));
+                    let class = HubError::class(&e).unwrap_or(ErrorClass::Submission);
+                    let record = state.failed(Uuid::new_v4(), class, msg);
+                    settle(&record, pbs, wtr)?;
+                    rejected.push(record);
+                },
             }
         }
+        wtr.flush()?;
 
-        if let Some(delay) = s.delay {
-            tokio::time::sleep(Duration::from_secs(delay)).await;
-        }
+        tokio::time::sleep(DASHBOARD.delay()).await;
     }
 
-    Ok(mints)
+    Ok((mints, rejected))
 }
 
 async fn handle_status(
@@ -118,130 +479,206 @@ async fn handle_status(
     mint_id: Uuid,
     state: &mut State,
     retry: bool,
+    max_retries: Option<u64>,
+    budget: Option<&Budget>,
     pbs: &HashMap<&'static str, ProgressBar>,
 ) -> Option<Record> {
-    match mint::check_status(hub, mint_id).await {
-        Ok(updated_mint_data) => match updated_mint_data.creation_status {
-            CreationStatus::CREATED => Some(Record {
-                mint_id,
-                completion_sec: state.start_time.elapsed().as_secs(),
-                retry_count: state.retry_count,
-                success: true,
-                reason: String::new(),
-            }),
-            CreationStatus::FAILED => {
-                if retry {
-                    let _ = mint::retry(hub, mint_id).await;
-                    pbs["retries"].inc(1);
-                    info!("Retrying FAILED mint {mint_id}");
-                    state.retry_count += 1;
-                }
-                state.last_pending_time = Instant::now();
-                Some(Record {
-                    mint_id,
-                    completion_sec: state.start_time.elapsed().as_secs(),
-                    retry_count: state.retry_count,
-                    success: false,
-                    reason: "backend was unable to mint".to_string(),
-                })
-            },
-            _ => None,
+    match mint::check_status(hub, mint_id, state).await {
+        Ok(updated_mint_data) => {
+            state.poll_timed_out = false;
+            let status = updated_mint_data.creation_status;
+            state.event(EventKind::Polled, Some(format!("{status:?}")));
+            match status {
+                CreationStatus::CREATED => {
+                    state.event(EventKind::Created, None);
+                    Some(state.created(mint_id))
+                },
+                CreationStatus::FAILED => {
+                    state.event(EventKind::Failed, None);
+                    let allowed = retry && state.retry_count < max_retries.unwrap_or(u64::MAX);
+                    let charged = allowed && budget.map(Budget::retry).unwrap_or(true);
+                    if allowed && !charged {
+                        warn!(
+                            mint_id:% = mint_id,
+                            operation = "retry";
+                            "Not retrying FAILED mint {mint_id}, it would exceed the budget"
+                        );
+                    }
+                    if charged {
+                        if let Err(e) = mint::retry(hub, mint_id, state).await {
+                            if let Some(budget) = budget.filter(|_| HubError::refused(&e)) {
+                                budget.refund_retry();
+                            }
+                            let msg = format!("Failed to retry mint {mint_id}: {e}");
+                            state.event(EventKind::Error, Some(msg.clone()));
+                            let class = HubError::class(&e).unwrap_or(ErrorClass::MintFailed);
+                            return Some(state.failed(mint_id, class, msg));
+                        }
+                        pbs["retries"].inc(1);
+                        METRICS.retried.inc();
+                        state.retry_count += 1;
+                        info!(
+                            mint_id:% = mint_id,
+                            operation = "retry",
+                            attempt = state.retry_count;
+                            "Retrying FAILED mint {mint_id}"
+                        );
+                        state.event(EventKind::Retried, None);
+                        state.last_pending_time = Instant::now();
+                        return None;
+                    }
+                    Some(state.failed(
+                        mint_id,
+                        ErrorClass::MintFailed,
+                        "backend was unable to mint".to_string(),
+                    ))
+                },
+                _ => None,
+            }
         },
         Err(e) => {
             let msg = format!("Failed to verify mint {}: {:?}", mint_id, e);
-            error!("{msg}");
-            Some(Record {
-                mint_id,
-                completion_sec: state.start_time.elapsed().as_secs(),
-                retry_count: state.retry_count,
-                success: false,
-                reason: msg,
-            })
+            error!(
+                mint_id:% = mint_id,
+                operation = "status",
+                attempt = state.retry_count,
+                error:% = e;
+                "{msg}"
+            );
+            state.event(EventKind::Error, Some(msg.clone()));
+            let class = HubError::class(&e);
+            state.poll_timed_out = class == Some(ErrorClass::Timeout);
+            // A slow poll says nothing about the mint. Keep polling, the
+            // pending timeout bounds how long we try.
+            if retry || state.poll_timed_out {
+                return None;
+            }
+            Some(state.failed(mint_id, class.unwrap_or(ErrorClass::StatusCheck), msg))
         },
     }
 }
 
+/// Polls every submitted mint until it reaches a terminal state, returning
+/// their records after the already settled `rejected` ones.
+///
+/// Each mint is polled on its own, `retry_delay` after its previous poll
+/// returned, so a slow poll only delays its own mint. Records are written to
+/// `wtr` as soon as their mint is settled and the writer is flushed every
+/// `retry_delay`, so the report can be tailed while the run is still in
+/// progress. The `pusher`, if any, periodically pushes the summary of the
+/// settled records.
+#[allow(clippy::too_many_arguments)]
 async fn verify(
     hub: &HubClient,
-    mints: &HashMap<Uuid, Instant>,
+    mints: HashMap<Uuid, State>,
+    rejected: Vec<Record>,
     s: &Settings,
     pbs: &HashMap<&'static str, ProgressBar>,
-) -> Vec<Record> {
+    wtr: &mut Writer,
+    pusher: &mut Option<Pusher>,
+    budget: Option<&Budget>,
+) -> Result<Vec<Record>> {
     let pending_timeout = tokio::time::Duration::from_secs(s.timeout.unwrap_or(400));
     let retry_delay = tokio::time::Duration::from_secs(s.retry_delay.unwrap_or(10));
-    let mut records = Vec::new();
+    let retry = s.retry.unwrap_or(false);
+    let max_retries = s.max_retries;
+    let mut records = rejected;
+
+    let poll = |mint_id: Uuid, mut state: State, wait: bool| async move {
+        if wait {
+            DASHBOARD.sleep(retry_delay).await;
+        }
+        let record = if DASHBOARD.abort() == Abort::Run {
+            None
+        } else if state.last_pending_time.elapsed() > pending_timeout {
+            let (class, cause) = if state.poll_timed_out {
+                (ErrorClass::Timeout, ", its last status poll timed out")
+            } else {
+                (ErrorClass::PendingTimeout, "")
+            };
+            let msg = format!(
+                "Mint {} is still pending after {} seconds{cause}",
+                mint_id,
+                pending_timeout.as_secs()
+            );
+            error!(
+                mint_id:% = mint_id,
+                status = "timed_out",
+                attempt = state.retry_count;
+                "{msg}"
+            );
+            state.event(EventKind::TimedOut, None);
+            Some(state.failed(mint_id, class, msg))
+        } else {
+            handle_status(hub, mint_id, &mut state, retry, max_retries, budget, pbs).await
+        };
+        (mint_id, state, record)
+    };
 
-    let mut pending_states: HashMap<Uuid, Arc<Mutex<State>>> = mints
+    let mut pending: HashMap<Uuid, Instant> = mints
         .iter()
-        .map(|(id, &time)| {
-            (
-                *id,
-                Arc::new(Mutex::new(State {
-                    start_time: time,
-                    last_pending_time: time,
-                    retry_count: 0,
-                })),
-            )
-        })
+        .map(|(&id, state)| (id, state.start_time))
         .collect();
+    let mut polls: FuturesUnordered<_> = mints
+        .into_iter()
+        .map(|(id, state)| poll(id, state, false))
+        .collect();
+    let mut aborted = 0;
+    // A zero period would panic, and flushing more often is pointless
+    let mut ticker = tokio::time::interval(retry_delay.max(Duration::from_millis(100)));
 
-    while records.len() < mints.len() {
-        let futures: FuturesUnordered<_> = pending_states
-            .iter()
-            .map(|(&mint_id, state)| {
-                let state = state.clone();
-                let hub = hub.clone();
-                let pbs = pbs.clone();
-                let retry = s.retry.unwrap_or(false);
-
-                async move {
-                    let mut locked_state = state.lock().unwrap();
-                    if locked_state.last_pending_time.elapsed() > pending_timeout {
-                        let msg = format!(
-                            "Mint {} is still pending after {} seconds",
-                            mint_id,
-                            pending_timeout.as_secs()
-                        );
-                        error!("{msg}");
-                        Some(Record {
+    while !polls.is_empty() {
+        tokio::select! {
+            Some((mint_id, mut state, record)) = polls.next() => {
+                let record = match record {
+                    Some(record) => record,
+                    None if DASHBOARD.abort() == Abort::Run => {
+                        aborted += 1;
+                        state.event(EventKind::Aborted, None);
+                        state.failed(
                             mint_id,
-                            completion_sec: locked_state.start_time.elapsed().as_secs(),
-                            retry_count: locked_state.retry_count,
-                            success: false,
-                            reason: msg,
-                        })
-                    } else {
-                        handle_status(&hub, mint_id, &mut locked_state, retry, &pbs).await
-                    }
+                            ErrorClass::Aborted,
+                            "run aborted while the mint was pending".to_string(),
+                        )
+                    },
+                    None => {
+                        polls.push(poll(mint_id, state, true));
+                        continue;
+                    },
+                };
+                pending.remove(&record.mint_id);
+                settle(&record, pbs, wtr)?;
+                records.push(record);
+            },
+            _ = ticker.tick() => {
+                wtr.flush()?;
+                DASHBOARD.in_flight(pending.iter().map(|(&id, start)| (id, start.elapsed())));
+                if let Some(pusher) = pusher {
+                    pusher.tick(&records, pending.len());
                 }
-            })
-            .collect();
-
-        let results: Vec<_> = futures.collect().await;
-        for record in results.into_iter().flatten() {
-            if record.success {
-                pbs["successful"].inc(1);
-                pending_states.remove(&record.mint_id);
-            } else if !record.success && !s.retry.unwrap_or(false) {
-                pbs["failed"].inc(1);
-                pending_states.remove(&record.mint_id);
-            }
-            records.push(record);
+            },
         }
-
-        tokio::time::sleep(retry_delay).await;
+    }
+    wtr.flush()?;
+    if aborted > 0 {
+        warn!("Run aborted, {aborted} pending mints left");
     }
 
-    records
+    Ok(records)
 }
 
-fn save(wtr: &mut Writer<File>, records: &Vec<Record>) -> Result<()> {
-    for record in records {
-        wtr.serialize(record)?;
+/// Counts a settled mint and appends it to the report
+fn settle(
+    record: &Record,
+    pbs: &HashMap<&'static str, ProgressBar>,
+    wtr: &mut Writer,
+) -> Result<()> {
+    if record.success {
+        pbs["successful"].inc(1);
+    } else {
+        pbs["failed"].inc(1);
     }
-    wtr.flush()?;
-
-    let cli = Opt::from_args();
-    info!("Report saved to {}", cli.global.output.display());
-    Ok(())
+    METRICS.settle(record);
+    DASHBOARD.settle(record);
+    wtr.write(record)
 }
@@ -0,0 +1,147 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::Result;
+
+use crate::{report::Run, stats};
+
+const TOP_REASONS: usize = 5;
+
+/// Writes a Markdown summary of the run, ready to paste on a pull request or
+/// in chat.
+///
+/// # Errors
+///
+/// Will return `Err` if the file can't be written
+pub fn save(path: &Path, run: &Run) -> Result<()> {
+    fs::write(path, render(run))?;
+    Ok(())
+}
+
+pub fn render(run: &Run) -> String {
+    let m = &run.metadata;
+    let s = &run.summary;
+    let settings = &m.settings;
+    let mut md = String::new();
+
+    let _ = writeln!(md, "## Benchy run `{}`\n", m.run_id);
+    md.push_str("| Setting | Value |\n|---|---|\n");
+    let rows = [
+        (
+            "Hub",
+            cell(m.config["hub"]["url"].as_str().unwrap_or_default()),
+        ),
+        (
+            "Profile",
+            cell(m.config["profile"].as_str().unwrap_or_default()),
+        ),
+        ("Benchy version", m.version.clone()),
+        ("Started", m.started_at.to_rfc3339()),
+        ("Duration", format!("{:.1}s", s.duration_sec)),
+        ("Iterations", opt(settings.iterations)),
+        ("Parallelism", opt(settings.parallelism)),
+        (
+            "Delay",
+            settings.delay.map(|d| format!("{d}s")).unwrap_or_default(),
+        ),
+        ("Retry", opt(settings.retry)),
+        (
+            "Timeout",
+            settings
+                .timeout
+                .map(|t| format!("{t}s"))
+                .unwrap_or_default(),
+        ),
+        ("Compressed", opt(m.config["mint"]["compressed"].as_bool())),
+        ("Transport", m.transport.to_string()),
+    ];
+    for (name, value) in rows {
+        let _ = writeln!(md, "| {name} | {value} |");
+    }
+
+    md.push_str("\n### Mints\n\n| Outcome | Count | Share |\n|---|---:|---:|\n");
+    for (outcome, count) in stats::outcomes(&run.records) {
+        let _ = writeln!(
+            md,
+            "| {outcome} | {count} | {:.1}% |",
+            stats::ratio(count, s.total) * 100.0
+        );
+    }
+    let _ = writeln!(md, "| **total** | {} | |", s.total);
+
+    let _ = writeln!(
+        md,
+        "\n**Success rate:** {:.1}% · **Throughput:** {:.3} mints/s · **Retries:** {}",
+        s.success_rate * 100.0,
+        s.throughput,
+        s.retries
+    );
+
+    md.push_str("\n### Completion time (successful mints)\n\n");
+    match &s.completion {
+        Some(p) => {
+            md.push_str("| min | mean | p50 | p90 | p95 | p99 | max |\n");
+            md.push_str("|---:|---:|---:|---:|---:|---:|---:|\n");
+            let _ = writeln!(
+                md,
+                "| {:.2}s | {:.2}s | {:.2}s | {:.2}s | {:.2}s | {:.2}s | {:.2}s |",
+                p.min, p.mean, p.p50, p.p90, p.p95, p.p99, p.max
+            );
+        },
+        None => md.push_str("No mint was created.\n"),
+    }
+
+    if let Some(c) = &s.credits {
+        md.push_str("\n### Credits\n\n| Credits | Value |\n|---|---:|\n");
+        let rows = [
+            ("Mint price", c.mint_cost.to_string()),
+            ("Retry price", opt(c.retry_cost)),
+            ("Expected", c.expected.to_string()),
+            ("Balance before", opt(c.balance_before)),
+            ("Balance after", opt(c.balance_after)),
+            ("Consumed", opt(c.actual)),
+            (
+                "Per created mint",
+                c.per_created_mint
+                    .map(|v| format!("{v:.2}"))
+                    .unwrap_or_default(),
+            ),
+        ];
+        for (name, value) in rows {
+            let _ = writeln!(md, "| {name} | {value} |");
+        }
+    }
+
+    let requests = &s.requests;
+    if !requests.statuses.is_empty() || !requests.errors.is_empty() {
+        md.push_str("\n### Hub requests\n\n| Response | Count |\n|---|---:|\n");
+        for (status, count) in &requests.statuses {
+            let _ = writeln!(md, "| HTTP {status} | {count} |");
+        }
+        for (class, count) in &requests.errors {
+            let _ = writeln!(md, "| {class} | {count} |");
+        }
+    }
+
+    let reasons = stats::top_reasons(&run.records, TOP_REASONS);
+    if !reasons.is_empty() {
+        md.push_str("\n### Top failure reasons\n\n| Reason | Count |\n|---|---:|\n");
+        for (reason, count) in reasons {
+            let _ = writeln!(md, "| {} | {count} |", cell(&reason));
+        }
+    }
+    md
+}
+
+/// Keeps a value from breaking out of its table cell or being rendered as
+/// HTML, e.g. the `<mint>` placeholder of the failure reasons
+fn cell(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\n', '\r'], " ")
+}
+
+fn opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,297 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use anyhow::Result;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, CounterVec, Encoder, Histogram, HistogramOpts, HistogramTimer, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::{
+    report::{ErrorClass, EventKind, Record},
+    stats::{Requests, Window},
+};
+
+/// Buckets of the mint completion histogram, in seconds
+const COMPLETION_BUCKETS: &[f64] = &[
+    1.0, 2.0, 5.0, 10.0, 15.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0, 180.0, 240.0, 300.0, 400.0,
+    600.0,
+];
+
+/// Span of the live rates and rolling percentiles
+const WINDOW: Duration = Duration::from_secs(60);
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Live counters of the run. They are always updated, the `/metrics`
+/// endpoint only exposes them when `--metrics-addr` is set.
+pub struct Metrics {
+    pub registry: Registry,
+    pub submitted: IntCounter,
+    pub created: IntCounter,
+    pub failed: IntCounterVec,
+    pub retried: IntCounter,
+    pub timed_out: IntCounter,
+    pub in_flight: IntGauge,
+    pub pending: IntGauge,
+    pub request_latency: HistogramVec,
+    pub completion_latency: Histogram,
+    pub responses: IntCounterVec,
+    pub request_errors: IntCounterVec,
+    pub throttled: IntCounterVec,
+    pub backoff: CounterVec,
+    window: Mutex<Window>,
+    requests: Mutex<Requests>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("benchy".to_string()), None)
+            .expect("valid metrics namespace");
+
+        let submitted =
+            IntCounter::new("mints_submitted_total", "Mint requests accepted by the Hub").unwrap();
+        let created = IntCounter::new("mints_created_total", "Mints created").unwrap();
+        let failed = IntCounterVec::new(
+            Opts::new("mints_failed_total", "Mints settled without being created"),
+            &["class"],
+        )
+        .unwrap();
+        let retried = IntCounter::new("mints_retried_total", "Retry requests sent").unwrap();
+        let timed_out = IntCounter::new(
+            "mints_timed_out_total",
+            "Mints still pending after the configured timeout",
+        )
+        .unwrap();
+        let in_flight = IntGauge::new("requests_in_flight", "Hub requests in flight").unwrap();
+        let pending = IntGauge::new("mints_pending", "Submitted mints not settled yet").unwrap();
+        let request_latency = HistogramVec::new(
+            HistogramOpts::new("request_duration_seconds", "Hub request latency"),
+            &["operation"],
+        )
+        .unwrap();
+        let completion_latency = Histogram::with_opts(
+            HistogramOpts::new(
+                "mint_completion_seconds",
+                "Time from submission to creation of a mint",
+            )
+            .buckets(COMPLETION_BUCKETS.to_vec()),
+        )
+        .unwrap();
+
+        let responses = IntCounterVec::new(
+            Opts::new("http_responses_total", "Hub responses by HTTP status"),
+            &["operation", "status"],
+        )
+        .unwrap();
+        let request_errors =
+            IntCounterVec::new(Opts::new("request_errors_total", "Failed Hub requests"), &[
+                "operation",
+                "class",
+            ])
+            .unwrap();
+
+        let throttled = IntCounterVec::new(
+            Opts::new(
+                "requests_throttled_total",
+                "Hub requests throttled and sent again",
+            ),
+            &["operation"],
+        )
+        .unwrap();
+        let backoff = CounterVec::new(
+            Opts::new(
+                "backoff_seconds_total",
+                "Time spent backing off throttled requests",
+            ),
+            &["operation"],
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(submitted.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(created.clone()),
+            Box::new(failed.clone()),
+            Box::new(retried.clone()),
+            Box::new(timed_out.clone()),
+            Box::new(in_flight.clone()),
+            Box::new(pending.clone()),
+            Box::new(request_latency.clone()),
+            Box::new(completion_latency.clone()),
+            Box::new(responses.clone()),
+            Box::new(request_errors.clone()),
+            Box::new(throttled.clone()),
+            Box::new(backoff.clone()),
+        ] {
+            registry.register(collector).unwrap();
+        }
+
+        Self {
+            registry,
+            submitted,
+            created,
+            failed,
+            retried,
+            timed_out,
+            in_flight,
+            pending,
+            request_latency,
+            completion_latency,
+            responses,
+            request_errors,
+            throttled,
+            backoff,
+            window: Mutex::new(Window::new(WINDOW)),
+            requests: Mutex::new(Requests::default()),
+        }
+    }
+
+    /// Counts a mint accepted by the Hub
+    pub fn submit(&self) {
+        self.submitted.inc();
+        self.pending.inc();
+        self.window().submit();
+    }
+
+    /// Counts a mint the Hub rejected, which is settled without ever being
+    /// submitted or pending
+    pub fn reject(&self) {
+        self.window().submit();
+    }
+
+    /// Counts a settled mint
+    pub fn settle(&self, record: &Record) {
+        if record.outcome() != EventKind::Rejected {
+            self.pending.dec();
+        }
+        self.window()
+            .settle(record.success.then(|| record.completion_secs()));
+        match record.error_class {
+            None => {
+                self.created.inc();
+                self.completion_latency.observe(record.completion_secs());
+            },
+            Some(class) => {
+                if class == ErrorClass::PendingTimeout {
+                    self.timed_out.inc();
+                }
+                self.failed.with_label_values(&[&class.to_string()]).inc();
+            },
+        }
+    }
+
+    /// Mints settled without being created, rejected ones included
+    pub fn failed_total(&self) -> u64 {
+        self.failed
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .map(|m| m.get_counter().get_value() as u64)
+            .sum()
+    }
+
+    /// Counts a Hub response by HTTP status
+    pub fn response(&self, operation: &str, status: u16) {
+        self.responses
+            .with_label_values(&[operation, &status.to_string()])
+            .inc();
+        *self.requests().statuses.entry(status).or_default() += 1;
+    }
+
+    /// Counts a failed Hub request
+    pub fn request_error(&self, operation: &str, class: ErrorClass) {
+        self.request_errors
+            .with_label_values(&[operation, &class.to_string()])
+            .inc();
+        *self.requests().errors.entry(class).or_default() += 1;
+    }
+
+    /// Counts a throttled request and its backoff
+    pub fn throttle(&self, operation: &str, wait: Duration) {
+        self.throttled.with_label_values(&[operation]).inc();
+        self.backoff
+            .with_label_values(&[operation])
+            .inc_by(wait.as_secs_f64());
+    }
+
+    pub fn requests(&self) -> MutexGuard<'_, Requests> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn window(&self) -> MutexGuard<'_, Window> {
+        self.window.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Tracks a Hub request until the returned guard is dropped
+    pub fn request(&self, operation: &str) -> InFlight {
+        self.in_flight.inc();
+        InFlight {
+            _timer: self
+                .request_latency
+                .with_label_values(&[operation])
+                .start_timer(),
+        }
+    }
+}
+
+/// Observes the request latency when dropped
+pub struct InFlight {
+    _timer: HistogramTimer,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        METRICS.in_flight.dec();
+    }
+}
+
+/// Serves the metrics in the Prometheus text format on `/metrics` until the
+/// process exits
+///
+/// # Errors
+///
+/// Will return `Err` if the address can't be bound
+pub fn serve(addr: SocketAddr) -> Result<()> {
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    let server = Server::try_bind(&addr)?.serve(make_svc);
+    info!("Serving metrics on http://{addr}/metrics");
+
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("Metrics server stopped: {e}");
+        }
+    });
+    Ok(())
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() != "/metrics" {
+        let mut res = Response::new(Body::from("Not found"));
+        *res.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(res);
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&METRICS.registry.gather(), &mut buffer) {
+        let mut res = Response::new(Body::from(e.to_string()));
+        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        return Ok(res);
+    }
+
+    let mut res = Response::new(Body::from(buffer));
+    res.headers_mut()
+        .insert(CONTENT_TYPE, encoder.format_type().parse().unwrap());
+    Ok(res)
+}
@@ -1,19 +1,122 @@
 use std::{str::FromStr, time::Instant};
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use log::{debug, error, info};
+use opentelemetry::{trace::TraceContextExt, Context, KeyValue};
 use uuid::Uuid;
 
-use crate::{config::Config, graphql::*, HubClient};
+use crate::{
+    config::Config,
+    graphql::*,
+    hub::Throttling,
+    metrics::METRICS,
+    otel,
+    report::{ErrorClass, Event, EventKind, Record},
+    HubClient,
+};
 
 #[derive(Clone)]
 pub struct State {
     pub start_time: Instant,
+    pub submitted_at: DateTime<Utc>,
     pub last_pending_time: Instant,
     pub retry_count: u64,
+    /// The last status poll timed out
+    pub poll_timed_out: bool,
+    pub timeline: Vec<Event>,
+    /// Context of the root span of the mint trace
+    pub trace: Context,
+    pub throttling: Throttling,
 }
 
-pub async fn execute(hub: &HubClient) -> Result<CollectionMint> {
+impl State {
+    pub fn new(start_time: Instant, submitted_at: DateTime<Utc>) -> Self {
+        Self {
+            start_time,
+            submitted_at,
+            last_pending_time: start_time,
+            retry_count: 0,
+            poll_timed_out: false,
+            timeline: vec![Event {
+                at: submitted_at,
+                kind: EventKind::Submitted,
+                detail: None,
+            }],
+            trace: otel::start(&Context::new(), "mint"),
+            throttling: Throttling::default(),
+        }
+    }
+
+    /// Sends a Hub request for the mint, recording its throttling in the
+    /// timeline
+    async fn post<T: serde::Serialize>(
+        &mut self,
+        hub: &HubClient,
+        operation: &'static str,
+        body: &T,
+        cx: &Context,
+    ) -> Result<String> {
+        let before = self.throttling;
+        let res = hub.post(operation, body, cx, &mut self.throttling).await;
+        let count = self.throttling.count - before.count;
+        if count > 0 {
+            let backoff = self.throttling.backoff - before.backoff;
+            self.event(
+                EventKind::Throttled,
+                Some(format!(
+                    "{operation} throttled {count} times, {:.1}s backoff",
+                    backoff.as_secs_f64()
+                )),
+            );
+        }
+        res
+    }
+
+    pub fn event(&mut self, kind: EventKind, detail: Option<String>) {
+        self.timeline.push(Event {
+            at: Utc::now(),
+            kind,
+            detail,
+        });
+    }
+
+    /// Settles the mint as created, handing its timeline over to the record
+    pub fn created(&mut self, mint_id: Uuid) -> Record {
+        self.record(mint_id, None, String::new())
+    }
+
+    /// Settles the mint as failed, handing its timeline over to the record
+    pub fn failed(&mut self, mint_id: Uuid, class: ErrorClass, reason: String) -> Record {
+        self.record(mint_id, Some(class), reason)
+    }
+
+    fn record(&mut self, mint_id: Uuid, error_class: Option<ErrorClass>, reason: String) -> Record {
+        let elapsed = self.start_time.elapsed();
+        let span = self.trace.span();
+        span.set_attribute(KeyValue::new("benchy.mint_id", mint_id.to_string()));
+        span.set_attribute(KeyValue::new("benchy.retry_count", self.retry_count as i64));
+        match error_class {
+            None => otel::end(&self.trace, Ok("CREATED".to_string())),
+            Some(class) => otel::end(&self.trace, Err(&anyhow!("{class}: {reason}"))),
+        }
+        Record {
+            mint_id,
+            completion_sec: elapsed.as_secs(),
+            retry_count: self.retry_count,
+            success: error_class.is_none(),
+            reason,
+            submitted_at: self.submitted_at,
+            completion_ms: elapsed.as_millis() as u64,
+            error_class,
+            throttled: self.throttling.count,
+            backoff_ms: self.throttling.backoff.as_millis() as u64,
+            timeline: std::mem::take(&mut self.timeline),
+        }
+    }
+}
+
+pub async fn execute(hub: &HubClient, state: &mut State) -> Result<CollectionMint> {
     let config = Config::read();
     let mc = config.mint.clone();
     let mutation = MintToCollection::build_query(mint_to_collection::Variables {
@@ -44,79 +147,119 @@ pub async fn execute(hub: &HubClient) -> Result<CollectionMint> {
         },
     });
 
-    let res_plain = hub
-        .client
-        .post(hub.url.clone())
-        .json(&mutation)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    process_response(&res_plain, |data: MintResponse| {
-        let cm = data.mint_to_collection.collection_mint.clone();
-        info!(
-            "Mint req sent successfully: MintID: {} -- Status: {}",
-            cm.id, cm.creation_status
-        );
-        Ok(CollectionMint {
-            id: cm.id,
-            creation_status: cm.creation_status,
+    let cx = otel::start(&state.trace, "mintToCollection");
+    let started = Instant::now();
+    let in_flight = METRICS.request("mint");
+    let res_plain = state.post(hub, "mint", &mutation, &cx).await;
+    drop(in_flight);
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let res = res_plain.and_then(|res_plain| {
+        process_response(&res_plain, "mint", |data: MintResponse| {
+            let cm = data.mint_to_collection.collection_mint.clone();
+            info!(
+                mint_id = cm.id.as_str(),
+                operation = "mint",
+                status = cm.creation_status.as_str(),
+                latency_ms;
+                "Mint req sent successfully: MintID: {} -- Status: {}",
+                cm.id, cm.creation_status
+            );
+            Ok(CollectionMint {
+                id: cm.id,
+                creation_status: cm.creation_status,
+            })
         })
-    })
+    });
+    if let Ok(cm) = &res {
+        cx.span()
+            .set_attribute(KeyValue::new("benchy.mint_id", cm.id.clone()));
+    }
+    otel::end(&cx, res.as_ref().map(|cm| cm.creation_status.clone()));
+    res
 }
 
-pub async fn retry(hub: &HubClient, id: Uuid) -> Result<CollectionMint> {
+pub async fn retry(hub: &HubClient, id: Uuid, state: &mut State) -> Result<CollectionMint> {
     let mutation = RetryMintToCollection::build_query(retry_mint_to_collection::Variables {
         input: RetryMintEditionInput { id },
     });
-    let res_plain = hub
-        .client
-        .post(hub.url.clone())
-        .json(&mutation)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    process_response(&res_plain, |data: RetryMintResponse| {
-        let cm = data.retry_mint_to_collection.collection_mint.clone();
-        info!(
-            "Retry Mint req sent successfully: MintID: {} -- Status: {}",
-            cm.id, cm.creation_status
-        );
-        Ok(CollectionMint {
-            id: cm.id,
-            creation_status: cm.creation_status,
+    let cx = otel::start(&state.trace, "retryMintToCollection");
+    cx.span()
+        .set_attribute(KeyValue::new("benchy.mint_id", id.to_string()));
+    let started = Instant::now();
+    let in_flight = METRICS.request("retry");
+    let res_plain = state.post(hub, "retry", &mutation, &cx).await;
+    drop(in_flight);
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let res = res_plain.and_then(|res_plain| {
+        process_response(&res_plain, "retry", |data: RetryMintResponse| {
+            let cm = data.retry_mint_to_collection.collection_mint.clone();
+            info!(
+                mint_id = cm.id.as_str(),
+                operation = "retry",
+                status = cm.creation_status.as_str(),
+                latency_ms;
+                "Retry Mint req sent successfully: MintID: {} -- Status: {}",
+                cm.id, cm.creation_status
+            );
+            Ok(CollectionMint {
+                id: cm.id,
+                creation_status: cm.creation_status,
+            })
         })
-    })
+    });
+    otel::end(&cx, res.as_ref().map(|cm| cm.creation_status.clone()));
+    res
 }
 
-pub async fn check_status(hub: &HubClient, id: Uuid) -> Result<MintData> {
+pub async fn check_status(hub: &HubClient, id: Uuid, state: &mut State) -> Result<MintData> {
     let query = MintStatus::build_query(mint_status::Variables { id });
-    let res_plain = hub
-        .client
-        .post(hub.url.clone())
-        .json(&query)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    process_response(&res_plain, |data: MintStatusResponse| {
-        let cm = data.mint;
-        debug!(
-            "Checking status of mint {} -- Status: {:?}",
-            cm.id, cm.creation_status
-        );
-        if cm.creation_status == CreationStatus::CREATED {
-            info!("Mint {} created successfully", cm.id);
-        }
-        Ok(cm)
-    })
+    let cx = otel::start(&state.trace, "mintStatus");
+    cx.span()
+        .set_attribute(KeyValue::new("benchy.mint_id", id.to_string()));
+    let started = Instant::now();
+    let in_flight = METRICS.request("status");
+    let res_plain = state.post(hub, "status", &query, &cx).await;
+    drop(in_flight);
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let res = res_plain.and_then(|res_plain| {
+        process_response(&res_plain, "status", |data: MintStatusResponse| {
+            let cm = data.mint;
+            debug!(
+                mint_id = cm.id.as_str(),
+                operation = "status",
+                status:? = cm.creation_status,
+                latency_ms;
+                "Checking status of mint {} -- Status: {:?}",
+                cm.id, cm.creation_status
+            );
+            if cm.creation_status == CreationStatus::CREATED {
+                info!(
+                    mint_id = cm.id.as_str(),
+                    operation = "status",
+                    status:? = cm.creation_status,
+                    latency_ms;
+                    "Mint {} created successfully",
+                    cm.id
+                );
+            }
+            Ok(cm)
+        })
+    });
+    otel::end(
+        &cx,
+        res.as_ref().map(|cm| format!("{:?}", cm.creation_status)),
+    );
+    res
 }
 
-fn process_response<T, R>(res_plain: &str, on_success: impl FnOnce(T) -> Result<R>) -> Result<R>
+pub fn process_response<T, R>(
+    res_plain: &str,
+    operation: &'static str,
+    on_success: impl FnOnce(T) -> Result<R>,
+) -> Result<R>
 where
     T: serde::de::DeserializeOwned,
 {
@@ -125,9 +268,9 @@ where
             errors: Some(errors),
             ..
         }) => {
-            error!("{}", res_plain);
-            let messages: Vec<_> = errors.iter().map(|e| &e.message).collect();
-            Err(anyhow!("GraphQL Errors: {:?}", messages))
+            let e = GraphQLErrors(errors.into_iter().map(|e| e.message).collect());
+            error!(operation, error:% = e; "{}", res_plain);
+            Err(e.into())
         },
         Ok(GraphQLResponse {
             data: Some(data), ..
@@ -137,7 +280,7 @@ where
                 "Unable to parse response. Operation failed with error: {}",
                 res_plain
             );
-            error!("{e}");
+            error!(operation, error = e.as_str(); "{e}");
             Err(anyhow!("{e}"))
         },
     }
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, Result};
+use log::{info, warn};
+use opentelemetry::{
+    global::{self, BoxedTracer},
+    trace::{Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use url::Url;
+
+/// Exports the mint spans to an OTLP/HTTP collector, `/v1/traces` is
+/// appended to the endpoint. Until this is called the global tracer is a
+/// no-op and no `traceparent` header is sent.
+///
+/// # Errors
+///
+/// Will return `Err` if the exporter can't be built
+pub fn init(endpoint: &Url) -> Result<()> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint.as_str().trim_end_matches('/')),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new([KeyValue::new("service.name", "benchy")])),
+        )
+        .install_batch(runtime::Tokio)?;
+    info!("Exporting traces to {endpoint}");
+    Ok(())
+}
+
+/// Flushes the spans still buffered by the batch exporter
+pub async fn shutdown() {
+    if let Err(e) = tokio::task::spawn_blocking(global::shutdown_tracer_provider).await {
+        warn!("Unable to flush traces: {e}");
+    }
+}
+
+pub fn tracer() -> BoxedTracer {
+    global::tracer("benchy")
+}
+
+/// Starts a span under `parent`, returning the context holding it
+pub fn start(parent: &Context, name: &'static str) -> Context {
+    parent.with_span(tracer().start_with_context(name, parent))
+}
+
+/// Ends the span of `cx`, recording the mint status or the error
+pub fn end(cx: &Context, outcome: Result<String, &Error>) {
+    let span = cx.span();
+    match outcome {
+        Ok(status) => span.set_attribute(KeyValue::new("benchy.mint.status", status)),
+        Err(e) => span.set_status(Status::error(e.to_string())),
+    }
+    span.end();
+}
+
+/// W3C trace context headers of the span of `cx`
+pub fn headers(cx: &Context) -> HeaderMap {
+    let mut fields = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut fields));
+
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(&value).ok()?,
+            ))
+        })
+        .collect()
+}
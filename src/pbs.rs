@@ -1,18 +1,74 @@
-use std::{collections::HashMap, fmt::Write};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    io::{self, IsTerminal},
+};
 
+use indicatif::ProgressDrawTarget;
 pub use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use log::info;
+use tokio::{task::JoinHandle, time::Duration};
 
-use crate::Record;
+use crate::{logger, metrics::METRICS, report::Record};
+
+/// Whether progress bars can be drawn, CI logs get status lines instead
+pub fn is_terminal() -> bool {
+    io::stdout().is_terminal() && io::stderr().is_terminal()
+}
+
+/// In headless mode the bars keep counting but are never drawn
+pub fn multi(headless: bool) -> MultiProgress {
+    if headless {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    }
+}
+
+/// Logs a one-line summary of the run every `interval` until aborted
+pub fn status(interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            log_status();
+        }
+    })
+}
+
+/// Status line with the mint counts and the live rates and estimates
+pub fn log_status() {
+    let submitted = METRICS.submitted.get();
+    let created = METRICS.created.get();
+    let pending = METRICS.pending.get().max(0) as u64;
+    let failed = METRICS.failed_total();
+    let live = METRICS.window().live();
+    info!(
+        target: logger::STATUS_TARGET,
+        submitted,
+        created,
+        failed,
+        pending,
+        submit_rate = live.submit_rate,
+        settle_rate = live.settle_rate,
+        p50 = live.p50,
+        p95 = live.p95,
+        eta = live.eta.map(|d| d.as_secs());
+        "submitted {submitted}, created {created}, failed {failed}, pending {pending}, {live}"
+    );
+}
 
 pub async fn init(
     m: &MultiProgress,
     total_mints: usize,
     retry: bool,
 ) -> HashMap<&'static str, ProgressBar> {
-    let style = ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] ({pos}/{len} {msg}, ETA {eta})")
-        .unwrap()
-        .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
-        .progress_chars("#>-");
+    let style = ProgressStyle::with_template(
+        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] ({pos}/{len} {msg})",
+    )
+    .unwrap()
+    .progress_chars("#>-");
 
     let mut progress_bars = HashMap::new();
 
@@ -46,6 +102,21 @@ pub async fn init(
         pb4.set_message("retries");
         progress_bars.insert("retries", pb4);
     }
+    let last = progress_bars
+        .get("retries")
+        .unwrap_or(&progress_bars["failed"])
+        .clone();
+    let live = m.insert_after(&last, ProgressBar::new_spinner());
+    live.set_style(
+        ProgressStyle::with_template("{spinner:.green} {live}")
+            .unwrap()
+            .with_key("live", |_: &ProgressState, w: &mut dyn Write| {
+                write!(w, "{}", METRICS.window().live()).unwrap();
+            }),
+    );
+    live.enable_steady_tick(Duration::from_millis(500));
+    progress_bars.insert("live", live);
+
     for i in progress_bars.iter() {
         i.1.tick();
     }
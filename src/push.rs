@@ -0,0 +1,221 @@
+use std::{fmt::Write, time::Instant};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use reqwest::{Client, RequestBuilder};
+use tokio::{task::JoinHandle, time::Duration};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    config::{PushConfig, PushKind},
+    report::Record,
+    stats::Summary,
+};
+
+/// Pushes are abandoned after this long
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushes summary metrics of the run to a Prometheus Pushgateway or an
+/// InfluxDB write endpoint, periodically and once the run is over
+pub struct Pusher {
+    client: Client,
+    config: PushConfig,
+    token: Option<String>,
+    run_id: Uuid,
+    started_at: DateTime<Utc>,
+    last_push: Option<Instant>,
+    in_flight: Option<JoinHandle<()>>,
+}
+
+impl Pusher {
+    pub fn new(config: PushConfig, run_id: Uuid, started_at: DateTime<Utc>) -> Self {
+        let token = config.token.as_ref().and_then(|token| {
+            token
+                .resolve()
+                .map_err(|e| warn!("Unable to read push.token, pushing without it: {e:#}"))
+                .ok()
+        });
+        Self {
+            client: Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            token,
+            config,
+            run_id,
+            started_at,
+            last_push: None,
+            in_flight: None,
+        }
+    }
+
+    /// Pushes the metrics of the records settled so far in the background if
+    /// the interval elapsed and the previous push is over. Failures are
+    /// logged and never interrupt the run.
+    pub fn tick(&mut self, records: &[Record], pending: usize) {
+        let interval = Duration::from_secs(self.config.interval.unwrap_or(30));
+        if self.last_push.is_some_and(|t| t.elapsed() < interval)
+            || self.in_flight.as_ref().is_some_and(|p| !p.is_finished())
+        {
+            return;
+        }
+        self.last_push = Some(Instant::now());
+
+        let elapsed = (Utc::now() - self.started_at).to_std().unwrap_or_default();
+        let summary = Summary::new(records, elapsed);
+        let url = self.config.url.clone();
+        match self.request(&summary, pending, false) {
+            Ok(req) => {
+                self.in_flight = Some(tokio::spawn(async move {
+                    if let Err(e) = send(req, &url).await {
+                        warn!("Unable to push metrics to {url}: {e}");
+                    }
+                }));
+            },
+            Err(e) => warn!("Unable to push metrics to {url}: {e}"),
+        }
+    }
+
+    pub async fn finish(&self, summary: &Summary) {
+        let res = match self.request(summary, 0, true) {
+            Ok(req) => send(req, &self.config.url).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            warn!("Unable to push final metrics to {}: {e}", self.config.url);
+        }
+    }
+
+    fn request(&self, summary: &Summary, pending: usize, finished: bool) -> Result<RequestBuilder> {
+        let values = values(summary, pending, finished);
+        let req = match self.config.kind {
+            PushKind::Pushgateway => self
+                .client
+                .put(self.pushgateway_url()?)
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .body(self.exposition(&values)),
+            PushKind::Influx => {
+                let req = self
+                    .client
+                    .post(self.config.url.clone())
+                    .body(self.line(&values));
+                match &self.token {
+                    Some(token) => req.header("Authorization", format!("Token {token}")),
+                    None => req,
+                }
+            },
+        };
+        Ok(req)
+    }
+
+    fn labels(&self) -> [(&'static str, String); 3] {
+        [
+            ("run_id", self.run_id.to_string()),
+            (
+                "workload",
+                self.config
+                    .workload
+                    .clone()
+                    .unwrap_or_else(|| "mint".to_string()),
+            ),
+            (
+                "environment",
+                self.config
+                    .environment
+                    .clone()
+                    .unwrap_or_else(|| "default".to_string()),
+            ),
+        ]
+    }
+
+    /// Grouping key path, `/metrics/job/benchy/run_id/<id>/...`
+    fn pushgateway_url(&self) -> Result<Url> {
+        let mut url = self.config.url.clone();
+        {
+            let mut path = url
+                .path_segments_mut()
+                .map_err(|()| anyhow!("Invalid Pushgateway url {}", self.config.url))?;
+            path.pop_if_empty().extend(["metrics", "job", "benchy"]);
+            for (name, value) in self.labels() {
+                path.push(name).push(&value);
+            }
+        }
+        Ok(url)
+    }
+
+    /// Prometheus text exposition format, labels come from the grouping key
+    fn exposition(&self, values: &[(&str, f64)]) -> String {
+        let mut body = String::new();
+        for (name, value) in values {
+            let _ = writeln!(
+                body,
+                "# TYPE benchy_run_{name} gauge\nbenchy_run_{name} {value}"
+            );
+        }
+        body
+    }
+
+    /// InfluxDB line protocol, a single `benchy_run` point
+    fn line(&self, values: &[(&str, f64)]) -> String {
+        let tags: Vec<String> = self
+            .labels()
+            .iter()
+            .map(|(name, value)| format!("{name}={}", escape_tag(value)))
+            .collect();
+        let fields: Vec<String> = values
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect();
+        format!(
+            "benchy_run,{} {} {}\n",
+            tags.join(","),
+            fields.join(","),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        )
+    }
+}
+
+async fn send(req: RequestBuilder, url: &Url) -> Result<()> {
+    let res = req.send().await?;
+    if !res.status().is_success() {
+        let status = res.status();
+        return Err(anyhow!(
+            "{status}: {}",
+            res.text().await.unwrap_or_default()
+        ));
+    }
+    debug!("Pushed metrics to {url}");
+    Ok(())
+}
+
+fn values(summary: &Summary, pending: usize, finished: bool) -> Vec<(&'static str, f64)> {
+    let mut values = vec![
+        ("mints_settled", summary.total as f64),
+        ("mints_created", summary.successful as f64),
+        ("mints_failed", summary.failed as f64),
+        ("mints_pending", pending as f64),
+        ("retries", summary.retries as f64),
+        ("success_rate", summary.success_rate),
+        ("throughput", summary.throughput),
+        ("duration_seconds", summary.duration_sec),
+        ("finished", if finished { 1.0 } else { 0.0 }),
+    ];
+    if let Some(p) = &summary.completion {
+        values.extend([
+            ("completion_p50_seconds", p.p50),
+            ("completion_p95_seconds", p.p95),
+            ("completion_p99_seconds", p.p99),
+            ("completion_max_seconds", p.max),
+        ]);
+    }
+    values
+}
+
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
@@ -0,0 +1,303 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    config::{Config, Settings, Transport},
+    csv::Row,
+    stats::Summary,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    Jsonl,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => Err(anyhow!(
+                "Unknown report format {s}, expected csv, json or jsonl"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// Id of the mint on the Hub, or a random one when its submission failed
+    pub mint_id: Uuid,
+    pub completion_sec: u64,
+    pub retry_count: u64,
+    pub success: bool,
+    pub reason: String,
+    pub submitted_at: DateTime<Utc>,
+    pub completion_ms: u64,
+    pub error_class: Option<ErrorClass>,
+    /// Requests of the mint throttled by the Hub and sent again
+    #[serde(default)]
+    pub throttled: u64,
+    /// Time spent backing off the throttled requests
+    #[serde(default)]
+    pub backoff_ms: u64,
+    #[serde(default)]
+    pub timeline: Vec<Event>,
+}
+
+impl Record {
+    pub fn completion_secs(&self) -> f64 {
+        self.completion_ms as f64 / 1000.0
+    }
+
+    /// Final state of the mint, taken from the last settling event of its timeline
+    pub fn outcome(&self) -> EventKind {
+        if self.success {
+            return EventKind::Created;
+        }
+        self.timeline
+            .iter()
+            .rev()
+            .map(|e| e.kind)
+            .find(|k| {
+                matches!(
+                    k,
+                    EventKind::Failed
+                        | EventKind::TimedOut
+                        | EventKind::Error
+                        | EventKind::Aborted
+                        | EventKind::Rejected
+                )
+            })
+            .unwrap_or(EventKind::Failed)
+    }
+}
+
+/// Why a mint did not get created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// The Hub reported the mint as `FAILED`
+    MintFailed,
+    /// The mint was still pending when `settings.timeout` elapsed
+    PendingTimeout,
+    /// The mint status could not be checked
+    StatusCheck,
+    /// The run was aborted from the dashboard while the mint was pending
+    Aborted,
+    /// The Hub refused the token, HTTP 401 or 403
+    Auth,
+    /// The Hub throttled the request, HTTP 429
+    RateLimited,
+    /// A gateway in front of the Hub failed, HTTP 502, 503 or 504
+    Gateway,
+    /// Any other HTTP 5xx
+    ServerError,
+    /// Any other HTTP 4xx
+    ClientError,
+    /// The request or its response took too long, or HTTP 408
+    Timeout,
+    /// The connection to the Hub could not be established
+    Connection,
+    /// The TLS handshake or certificate verification failed
+    Tls,
+    /// The connection broke while sending the request or reading the response
+    Transport,
+    /// The Hub answered the mint submission without a mint, e.g. with
+    /// GraphQL errors
+    Submission,
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::MintFailed => "mint_failed",
+            Self::PendingTimeout => "pending_timeout",
+            Self::StatusCheck => "status_check",
+            Self::Aborted => "aborted",
+            Self::Auth => "auth",
+            Self::RateLimited => "rate_limited",
+            Self::Gateway => "gateway",
+            Self::ServerError => "server_error",
+            Self::ClientError => "client_error",
+            Self::Timeout => "timeout",
+            Self::Connection => "connection",
+            Self::Tls => "tls",
+            Self::Transport => "transport",
+            Self::Submission => "submission",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Submitted,
+    Polled,
+    Retried,
+    Throttled,
+    Created,
+    Failed,
+    TimedOut,
+    Error,
+    Aborted,
+    /// The mint submission failed, the Hub never knew the mint
+    Rejected,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Submitted => "submitted",
+            Self::Polled => "polled",
+            Self::Retried => "retried",
+            Self::Throttled => "throttled",
+            Self::Created => "created",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Error => "error",
+            Self::Aborted => "aborted",
+            Self::Rejected => "rejected",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    pub run_id: Uuid,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub settings: Settings,
+    /// Snapshot of the config file, literal secrets are redacted
+    pub config: serde_json::Value,
+    /// SHA-256 of the config snapshot and the settings shaping the workload,
+    /// runs sharing it ran the same workload
+    #[serde(default)]
+    pub config_fingerprint: String,
+    /// Connections of the Hub requests, defaults included
+    #[serde(default)]
+    pub transport: Transport,
+}
+
+impl Metadata {
+    pub fn new(run_id: Uuid, settings: &Settings) -> Result<Self> {
+        let cfg = Config::read();
+        let config = serde_json::to_value(cfg)?;
+        // How the run is displayed or observed doesn't change its workload
+        let workload = Settings {
+            log_level: None,
+            metrics_addr: None,
+            otlp_endpoint: None,
+            headless: None,
+            status_interval: None,
+            tui: None,
+            ..settings.clone()
+        };
+        let fingerprinted = serde_json::json!({ "config": config, "settings": workload });
+        let config_fingerprint = format!("{:x}", Sha256::digest(fingerprinted.to_string()));
+
+        Ok(Self {
+            run_id,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: Utc::now(),
+            finished_at: None,
+            settings: settings.clone(),
+            config,
+            config_fingerprint,
+            transport: cfg.hub.transport.resolved(),
+        })
+    }
+}
+
+/// Complete result of a run as written by the `json` format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub metadata: Metadata,
+    pub summary: Summary,
+    pub records: Vec<Record>,
+}
+
+pub enum Writer {
+    Csv(Box<crate::csv::Writer<File>>),
+    Json(BufWriter<File>),
+    Jsonl(BufWriter<File>),
+}
+
+impl Writer {
+    /// # Errors
+    ///
+    /// Will return `Err` if the output file can't be created
+    pub fn from_path(path: &Path, format: Format) -> Result<Self> {
+        Ok(match format {
+            Format::Csv => Self::Csv(Box::new(crate::csv::Writer::from_path(path)?)),
+            Format::Json => Self::Json(BufWriter::new(File::create(path)?)),
+            Format::Jsonl => Self::Jsonl(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Appends a settled mint to the report. The `json` format is a single
+    /// document so its records are only written by [`Writer::finish`].
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        match self {
+            Self::Csv(wtr) => wtr.serialize(Row::from(record))?,
+            Self::Jsonl(wtr) => {
+                serde_json::to_writer(&mut *wtr, record)?;
+                wtr.write_all(b"\n")?;
+            },
+            Self::Json(_) => {},
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Csv(wtr) => wtr.flush()?,
+            Self::Json(wtr) | Self::Jsonl(wtr) => wtr.flush()?,
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self, run: &Run) -> Result<()> {
+        if let Self::Json(wtr) = &mut self {
+            serde_json::to_writer_pretty(&mut *wtr, run)?;
+            wtr.write_all(b"\n")?;
+        }
+        self.flush()
+    }
+}
+
+/// Writes a complete run at once, used for runs loaded from the history
+pub fn save(path: &Path, format: Format, run: &Run) -> Result<()> {
+    let mut wtr = Writer::from_path(path, format)?;
+    for record in &run.records {
+        wtr.write(record)?;
+    }
+    wtr.finish(run)
+}
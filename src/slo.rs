@@ -0,0 +1,141 @@
+use crate::{config::Assertions, report::Run, stats};
+
+/// Result of a single assertion
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+pub fn evaluate(assertions: &Assertions, run: &Run) -> Vec<Check> {
+    let s = &run.summary;
+    let mut checks = Vec::new();
+
+    if let Some(min) = assertions.min_success_rate {
+        checks.push(Check {
+            name: "min_success_rate".to_string(),
+            passed: s.success_rate >= min,
+            message: format!(
+                "success rate {:.2}% (minimum {:.2}%)",
+                s.success_rate * 100.0,
+                min * 100.0
+            ),
+        });
+    }
+
+    if let Some(max) = assertions.max_p95_completion {
+        checks.push(match &s.completion {
+            Some(p) => Check {
+                name: "max_p95_completion".to_string(),
+                passed: p.p95 <= max,
+                message: format!("p95 completion {:.2}s (maximum {max:.2}s)", p.p95),
+            },
+            None => Check {
+                name: "max_p95_completion".to_string(),
+                passed: false,
+                message: "no mint was created".to_string(),
+            },
+        });
+    }
+
+    for (class, max) in &assertions.max_error_rate {
+        let count = run
+            .records
+            .iter()
+            .filter(|r| r.error_class == Some(*class))
+            .count();
+        let rate = stats::ratio(count, s.total);
+        checks.push(Check {
+            name: format!("max_error_rate.{class}"),
+            passed: rate <= *max,
+            message: format!(
+                "{class} error rate {:.2}% (maximum {:.2}%)",
+                rate * 100.0,
+                max * 100.0
+            ),
+        });
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        report::{ErrorClass, Event, EventKind, Metadata, Record},
+        stats::Summary,
+    };
+
+    fn record(error_class: Option<ErrorClass>, outcome: EventKind) -> Record {
+        let at = Utc::now();
+        Record {
+            mint_id: Uuid::new_v4(),
+            completion_sec: 1,
+            retry_count: 0,
+            success: error_class.is_none(),
+            reason: String::new(),
+            submitted_at: at,
+            completion_ms: 1000,
+            error_class,
+            throttled: 0,
+            backoff_ms: 0,
+            timeline: vec![Event {
+                at,
+                kind: outcome,
+                detail: None,
+            }],
+        }
+    }
+
+    fn run(records: Vec<Record>) -> Run {
+        Run {
+            metadata: Metadata {
+                run_id: Uuid::new_v4(),
+                version: String::new(),
+                started_at: Utc::now(),
+                finished_at: None,
+                settings: serde_json::from_str("{}").unwrap(),
+                config: serde_json::Value::Null,
+                config_fingerprint: String::new(),
+                transport: Default::default(),
+            },
+            summary: Summary::new(&records, Duration::from_secs(10)),
+            records,
+        }
+    }
+
+    fn min_success_rate(min: f64, records: Vec<Record>) -> Check {
+        let assertions = Assertions {
+            min_success_rate: Some(min),
+            ..Assertions::default()
+        };
+        evaluate(&assertions, &run(records)).remove(0)
+    }
+
+    #[test]
+    fn min_success_rate_counts_rejected_submissions() {
+        let mut records = vec![record(None, EventKind::Created)];
+        records.extend((0..9).map(|_| record(Some(ErrorClass::ServerError), EventKind::Rejected)));
+
+        let check = min_success_rate(0.5, records);
+        assert!(!check.passed);
+        assert_eq!(check.message, "success rate 10.00% (minimum 50.00%)");
+    }
+
+    #[test]
+    fn min_success_rate_passes_at_the_minimum() {
+        let records = vec![
+            record(None, EventKind::Created),
+            record(Some(ErrorClass::Timeout), EventKind::Rejected),
+        ];
+
+        assert!(min_success_rate(0.5, records).passed);
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use chrono::Duration as ChronoDuration;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    credits::CreditUsage,
+    report::{ErrorClass, EventKind, Record},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub total: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub retries: u64,
+    /// Requests throttled by the Hub and sent again
+    #[serde(default)]
+    pub throttled: u64,
+    /// Time spent backing off throttled requests, in seconds
+    #[serde(default)]
+    pub backoff_sec: f64,
+    pub success_rate: f64,
+    pub duration_sec: f64,
+    /// Successful mints per second over the whole run
+    pub throughput: f64,
+    /// Completion time of successful mints, in seconds
+    pub completion: Option<Percentiles>,
+    /// Credits of the run, when the credit sheet could be fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credits: Option<CreditUsage>,
+    /// Responses and failures of the Hub requests, including polls and
+    /// rejected submissions that left no record
+    #[serde(default)]
+    pub requests: Requests,
+}
+
+/// HTTP status codes and failures of the Hub requests of a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Requests {
+    /// Responses by HTTP status code
+    pub statuses: BTreeMap<u16, u64>,
+    /// Failed requests by error class
+    pub errors: BTreeMap<ErrorClass, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn new(records: &[Record], duration: Duration) -> Self {
+        let total = records.len();
+        let successful = records.iter().filter(|r| r.success).count();
+        let duration_sec = duration.as_secs_f64();
+        let completion: Vec<f64> = records
+            .iter()
+            .filter(|r| r.success)
+            .map(Record::completion_secs)
+            .collect();
+
+        Self {
+            total,
+            successful,
+            failed: total - successful,
+            retries: records.iter().map(|r| r.retry_count).sum(),
+            throttled: records.iter().map(|r| r.throttled).sum(),
+            backoff_sec: records.iter().map(|r| r.backoff_ms).sum::<u64>() as f64 / 1000.0,
+            success_rate: ratio(successful, total),
+            duration_sec,
+            throughput: if duration_sec > 0.0 {
+                successful as f64 / duration_sec
+            } else {
+                0.0
+            },
+            completion: Percentiles::new(completion),
+            credits: None,
+            requests: Requests::default(),
+        }
+    }
+}
+
+impl Percentiles {
+    pub fn new(mut samples: Vec<f64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_by(f64::total_cmp);
+
+        Some(Self {
+            min: samples[0],
+            mean: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50: percentile(&samples, 50.0),
+            p90: percentile(&samples, 90.0),
+            p95: percentile(&samples, 95.0),
+            p99: percentile(&samples, 99.0),
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+/// Nearest-rank percentile of an already sorted, non-empty slice
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Number of mints per final state
+pub fn outcomes(records: &[Record]) -> BTreeMap<EventKind, usize> {
+    let mut counts = BTreeMap::new();
+    for r in records {
+        *counts.entry(r.outcome()).or_default() += 1;
+    }
+    counts
+}
+
+/// Most frequent failure reasons, with mint ids masked so the same failure on
+/// different mints is counted together
+pub fn top_reasons(records: &[Record], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for r in records.iter().filter(|r| !r.success) {
+        let reason = r.reason.replace(&r.mint_id.to_string(), "<mint>");
+        *counts.entry(reason).or_default() += 1;
+    }
+
+    let mut reasons: Vec<_> = counts.into_iter().collect();
+    reasons.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    reasons.truncate(limit);
+    reasons
+}
+
+/// Wall time covered by the records, from the first submission to the last
+/// settled mint. Used when a result set has no run metadata.
+pub fn span(records: &[Record]) -> Duration {
+    let start = records.iter().map(|r| r.submitted_at).min();
+    let end = records
+        .iter()
+        .map(|r| r.submitted_at + ChronoDuration::milliseconds(r.completion_ms as i64))
+        .max();
+    match (start, end) {
+        (Some(start), Some(end)) => (end - start).to_std().unwrap_or_default(),
+        _ => Duration::ZERO,
+    }
+}
+
+/// Submissions and settlements of the last `span`, for live estimates that
+/// keep meaning something once every mint is submitted
+pub struct Window {
+    span: Duration,
+    started: Option<Instant>,
+    /// Mints planned for the run
+    total: usize,
+    submitted: usize,
+    settled: usize,
+    submissions: VecDeque<Instant>,
+    /// Settlement time and completion time in seconds of created mints
+    settlements: VecDeque<(Instant, Option<f64>)>,
+}
+
+/// Live rates and estimates over the window
+#[derive(Debug, Clone, Default)]
+pub struct Live {
+    /// Mints submitted per second
+    pub submit_rate: f64,
+    /// Mints settled per second
+    pub settle_rate: f64,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    /// Time left to settle every planned mint
+    pub eta: Option<Duration>,
+}
+
+impl Window {
+    pub fn new(span: Duration) -> Self {
+        Self {
+            span,
+            started: None,
+            total: 0,
+            submitted: 0,
+            settled: 0,
+            submissions: VecDeque::new(),
+            settlements: VecDeque::new(),
+        }
+    }
+
+    pub fn plan(&mut self, total: usize) {
+        self.total = total;
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn submit(&mut self) {
+        self.submitted += 1;
+        self.submissions.push_back(Instant::now());
+    }
+
+    /// `completion` is the completion time of a created mint, in seconds
+    pub fn settle(&mut self, completion: Option<f64>) {
+        self.settled += 1;
+        self.settlements.push_back((Instant::now(), completion));
+    }
+
+    /// Rates over the window, rolling completion percentiles of the created
+    /// mints, and an ETA assuming the current rates hold: the slowest of
+    /// submitting the mints left at the submission rate and settling every
+    /// outstanding mint at the settlement rate
+    pub fn live(&mut self) -> Live {
+        let now = Instant::now();
+        while self
+            .submissions
+            .front()
+            .is_some_and(|t| now - *t > self.span)
+        {
+            self.submissions.pop_front();
+        }
+        while self
+            .settlements
+            .front()
+            .is_some_and(|(t, _)| now - *t > self.span)
+        {
+            self.settlements.pop_front();
+        }
+
+        let elapsed = self
+            .started
+            .map_or(Duration::ZERO, |t| (now - t).min(self.span))
+            .as_secs_f64();
+        let rate = |count: usize| {
+            if elapsed > 0.0 {
+                count as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        let submit_rate = rate(self.submissions.len());
+        let settle_rate = rate(self.settlements.len());
+
+        let mut completion: Vec<f64> = self.settlements.iter().filter_map(|(_, c)| *c).collect();
+        completion.sort_by(f64::total_cmp);
+        let (p50, p95) = if completion.is_empty() {
+            (None, None)
+        } else {
+            (
+                Some(percentile(&completion, 50.0)),
+                Some(percentile(&completion, 95.0)),
+            )
+        };
+
+        let unsubmitted = self.total.saturating_sub(self.submitted) as f64;
+        let outstanding = self.total.saturating_sub(self.settled) as f64;
+        let eta = if outstanding == 0.0 {
+            Some(Duration::ZERO)
+        } else if settle_rate > 0.0 && (unsubmitted == 0.0 || submit_rate > 0.0) {
+            let submitting = if unsubmitted > 0.0 {
+                unsubmitted / submit_rate
+            } else {
+                0.0
+            };
+            Some(Duration::from_secs_f64(
+                submitting.max(outstanding / settle_rate),
+            ))
+        } else {
+            None
+        };
+
+        Live {
+            submit_rate,
+            settle_rate,
+            p50,
+            p95,
+            eta,
+        }
+    }
+}
+
+impl fmt::Display for Live {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{v:.1}s"));
+        write!(
+            f,
+            "{:.2} submitted/s, {:.2} settled/s, p50 {}, p95 {}, ETA {}",
+            self.submit_rate,
+            self.settle_rate,
+            secs(self.p50),
+            secs(self.p95),
+            secs(self.eta.map(|d| d.as_secs_f64()))
+        )
+    }
+}
+
+/// One-sided Mann-Whitney U test of whether `candidate` tends to be larger
+/// than `baseline`
+#[derive(Debug, Clone)]
+pub struct MannWhitney {
+    /// Number of (baseline, candidate) pairs where the candidate is larger,
+    /// ties counting as half
+    pub u: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+impl MannWhitney {
+    /// Uses the normal approximation with tie correction, `None` when either
+    /// sample is empty or every value is tied
+    pub fn new(baseline: &[f64], candidate: &[f64]) -> Option<Self> {
+        let (n1, n2) = (baseline.len() as f64, candidate.len() as f64);
+        if baseline.is_empty() || candidate.is_empty() {
+            return None;
+        }
+
+        let mut all: Vec<(f64, bool)> = baseline
+            .iter()
+            .map(|v| (*v, false))
+            .chain(candidate.iter().map(|v| (*v, true)))
+            .collect();
+        all.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let n = all.len() as f64;
+        let mut candidate_ranks = 0.0;
+        let mut ties = 0.0;
+        let mut i = 0;
+        while i < all.len() {
+            let mut j = i;
+            while j + 1 < all.len() && all[j + 1].0 == all[i].0 {
+                j += 1;
+            }
+            // Tied values share the average of their 1-based ranks
+            let rank = (i + j) as f64 / 2.0 + 1.0;
+            let t = (j - i + 1) as f64;
+            ties += t.powi(3) - t;
+            candidate_ranks += rank * all[i..=j].iter().filter(|(_, c)| *c).count() as f64;
+            i = j + 1;
+        }
+
+        let u = candidate_ranks - n2 * (n2 + 1.0) / 2.0;
+        let mean = n1 * n2 / 2.0;
+        let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+        if variance <= 0.0 {
+            return None;
+        }
+
+        // Continuity correction towards the mean
+        let diff = u - mean;
+        let correction = if diff > 0.0 {
+            0.5
+        } else if diff < 0.0 {
+            -0.5
+        } else {
+            0.0
+        };
+        let z = (diff - correction) / variance.sqrt();
+        Some(Self {
+            u,
+            z,
+            p_value: 1.0 - normal_cdf(z),
+        })
+    }
+}
+
+fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, Numerical Recipes' Chebyshev approximation
+/// with a relative error below 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98
+                                    + t * (1.488_515_87
+                                        + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One-sided values of the normal approximation with tie and continuity
+    /// corrections, computed by hand
+    fn assert_mann_whitney(baseline: &[f64], candidate: &[f64], u: f64, z: f64, p_value: f64) {
+        let mw = MannWhitney::new(baseline, candidate).unwrap();
+        assert_eq!(mw.u, u);
+        assert!((mw.z - z).abs() < 1e-9, "z {} != {z}", mw.z);
+        assert!(
+            (mw.p_value - p_value).abs() < 1e-6,
+            "p {} != {p_value}",
+            mw.p_value
+        );
+    }
+
+    #[test]
+    fn mann_whitney_separated_samples() {
+        assert_mann_whitney(
+            &[1.0, 2.0, 3.0, 4.0, 5.0],
+            &[6.0, 7.0, 8.0, 9.0, 10.0],
+            25.0,
+            2.506_718_245_762,
+            0.006_092_890_177_672,
+        );
+    }
+
+    #[test]
+    fn mann_whitney_ties() {
+        assert_mann_whitney(
+            &[1.0, 2.0, 2.0, 3.0, 4.0, 4.5],
+            &[2.0, 3.0, 3.0, 5.0, 6.0],
+            21.0,
+            1.022_928_955_401,
+            0.153_170_718_913_856,
+        );
+    }
+
+    #[test]
+    fn mann_whitney_faster_candidate() {
+        // The continuity correction goes towards the mean on both sides
+        assert_mann_whitney(
+            &[5.0, 6.0, 7.0, 8.0],
+            &[1.0, 2.0, 3.0, 4.5],
+            0.0,
+            -2.165_063_509_461,
+            0.984_808_589_011_711,
+        );
+    }
+
+    #[test]
+    fn mann_whitney_identical_samples() {
+        assert_mann_whitney(&[1.0, 2.0], &[1.0, 2.0], 2.0, 0.0, 0.5);
+    }
+
+    #[test]
+    fn mann_whitney_degenerate_samples() {
+        assert!(MannWhitney::new(&[], &[1.0]).is_none());
+        assert!(MannWhitney::new(&[1.0], &[]).is_none());
+        assert!(MannWhitney::new(&[3.0, 3.0], &[3.0]).is_none());
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use log::{Level, Log, Metadata, Record as LogRecord};
+use once_cell::sync::Lazy;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{BarChart, Block, Borders, List, ListItem, Paragraph, Sparkline},
+    Frame, Terminal,
+};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{metrics::METRICS, report::Record};
+
+/// Samples kept by the sparklines, one per second
+const HISTORY: usize = 300;
+const ERRORS: usize = 100;
+const SLOWEST: usize = 10;
+/// Batch delay change of the `+` and `-` keys
+const DELAY_STEP: Duration = Duration::from_millis(500);
+
+pub static DASHBOARD: Lazy<Dashboard> = Lazy::new(Dashboard::default);
+
+/// How far the run was asked to stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abort {
+    None,
+    /// Stop submitting mints, keep polling the pending ones
+    Submission,
+    /// Stop waiting, pending mints are settled as aborted
+    Run,
+}
+
+/// State shown by the dashboard and controls it steers the run with. The run
+/// always goes through it, the controls only change while it is open.
+#[derive(Default)]
+pub struct Dashboard {
+    open: AtomicBool,
+    paused: AtomicBool,
+    abort: AtomicU8,
+    /// Delay between iterations, in milliseconds
+    delay_ms: AtomicU64,
+    board: Mutex<Board>,
+}
+
+#[derive(Default)]
+struct Board {
+    /// Mints created during each second
+    throughput: VecDeque<u64>,
+    /// Mean completion time of the mints created during each second, in ms
+    latency: VecDeque<u64>,
+    created: u64,
+    completion_ms: u64,
+    outcomes: BTreeMap<String, u64>,
+    slowest: Vec<(Uuid, Duration)>,
+    errors: VecDeque<String>,
+}
+
+impl Dashboard {
+    pub fn set_delay(&self, delay: Duration) {
+        self.delay_ms
+            .store(delay.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn abort(&self) -> Abort {
+        match self.abort.load(Ordering::Relaxed) {
+            0 => Abort::None,
+            1 => Abort::Submission,
+            _ => Abort::Run,
+        }
+    }
+
+    /// Sleeps for `duration`, or until the run is aborted
+    pub async fn sleep(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        while self.abort() != Abort::Run {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            tokio::time::sleep(left.min(Duration::from_millis(200))).await;
+        }
+    }
+
+    /// Counts a settled mint
+    pub fn settle(&self, record: &Record) {
+        let mut board = self.board();
+        let outcome = record
+            .error_class
+            .map_or_else(|| "created".to_string(), |c| c.to_string());
+        *board.outcomes.entry(outcome).or_default() += 1;
+        if record.success {
+            board.created += 1;
+            board.completion_ms += record.completion_ms;
+        } else {
+            board.error(format!("{}: {}", record.mint_id, record.reason));
+        }
+    }
+
+    /// Keeps the mints pending for the longest time
+    pub fn in_flight(&self, pending: impl Iterator<Item = (Uuid, Duration)>) {
+        let mut slowest: Vec<_> = pending.collect();
+        slowest.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+        slowest.truncate(SLOWEST);
+        self.board().slowest = slowest;
+    }
+
+    fn board(&self) -> MutexGuard<'_, Board> {
+        self.board
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn key(&self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            KeyCode::Char('p' | ' ') => {
+                self.paused.fetch_xor(true, Ordering::Relaxed);
+            },
+            KeyCode::Char('+' | '=') => self.set_delay(self.delay().saturating_sub(DELAY_STEP)),
+            KeyCode::Char('-') => self.set_delay(self.delay() + DELAY_STEP),
+            KeyCode::Char('q') | KeyCode::Esc => self.stop(),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => self.stop(),
+            _ => {},
+        }
+    }
+
+    /// Stops the submission, or the whole run once the submission is stopped
+    fn stop(&self) {
+        let _ = self
+            .abort
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |a| {
+                Some((a + 1).min(2))
+            });
+    }
+
+    /// Closes the current second of the sparklines
+    fn sample(&self) {
+        let mut board = self.board();
+        let (created, completion_ms) = (board.created, board.completion_ms);
+        push(&mut board.throughput, created);
+        push(
+            &mut board.latency,
+            completion_ms.checked_div(created).unwrap_or(0),
+        );
+        board.created = 0;
+        board.completion_ms = 0;
+    }
+}
+
+impl Board {
+    fn error(&mut self, message: String) {
+        if self.errors.len() == ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(message);
+    }
+}
+
+fn push(samples: &mut VecDeque<u64>, value: u64) {
+    if samples.len() == HISTORY {
+        samples.pop_front();
+    }
+    samples.push_back(value);
+}
+
+/// Takes over the terminal until `close`, logs are only kept for the recent
+/// errors panel meanwhile
+///
+/// # Errors
+///
+/// Will return `Err` if the terminal can't be set up
+pub fn open(run_id: Uuid) -> Result<JoinHandle<Result<()>>> {
+    // A panic would otherwise leave the terminal in raw mode on the
+    // alternate screen, hiding its message
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if DASHBOARD.open.swap(false, Ordering::Relaxed) {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        }
+        default_hook(info);
+    }));
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    DASHBOARD.open.store(true, Ordering::Relaxed);
+
+    Ok(tokio::task::spawn_blocking(move || {
+        let res = draw_loop(&mut terminal, run_id);
+        DASHBOARD.open.store(false, Ordering::Relaxed);
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        res
+    }))
+}
+
+/// Gives the terminal back
+pub async fn close(dashboard: JoinHandle<Result<()>>) -> Result<()> {
+    DASHBOARD.open.store(false, Ordering::Relaxed);
+    dashboard.await?
+}
+
+fn draw_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, run_id: Uuid) -> Result<()> {
+    let started = Instant::now();
+    let mut last_sample = Instant::now();
+
+    while DASHBOARD.open.load(Ordering::Relaxed) {
+        terminal.draw(|f| draw(f, run_id, started.elapsed()))?;
+
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    DASHBOARD.key(key.code, key.modifiers);
+                }
+            }
+        }
+        if last_sample.elapsed() >= Duration::from_secs(1) {
+            last_sample = Instant::now();
+            DASHBOARD.sample();
+        }
+    }
+    Ok(())
+}
+
+fn draw(f: &mut Frame<'_>, run_id: Uuid, elapsed: Duration) {
+    let board = DASHBOARD.board();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(8),
+            Constraint::Min(8),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let state = match (DASHBOARD.abort(), DASHBOARD.paused()) {
+        (Abort::Run, _) => "aborting",
+        (Abort::Submission, _) => "submission stopped, waiting for pending mints",
+        (Abort::None, true) => "submission paused",
+        (Abort::None, false) => "running",
+    };
+    let submitted = METRICS.submitted.get();
+    let created = METRICS.created.get();
+    let pending = METRICS.pending.get().max(0) as u64;
+    let header = Paragraph::new(vec![
+        Line::from(format!(
+            "run {run_id}  elapsed {}s  {state}",
+            elapsed.as_secs()
+        )),
+        Line::from(format!(
+            "submitted {submitted}  created {created}  failed {}  pending {pending}  batch delay {:.1}s",
+            METRICS.failed_total(),
+            DASHBOARD.delay().as_secs_f64()
+        )),
+        Line::from(METRICS.window().live().to_string()),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("benchy"));
+    f.render_widget(header, rows[0]);
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+    let throughput: Vec<u64> = recent(&board.throughput, charts[0].width);
+    f.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Throughput, {} mints/s",
+                throughput.last().copied().unwrap_or_default()
+            )))
+            .data(&throughput)
+            .style(Style::default().fg(Color::Cyan)),
+        charts[0],
+    );
+    let latency: Vec<u64> = recent(&board.latency, charts[1].width);
+    f.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Completion time, {:.1}s",
+                latency.last().copied().unwrap_or_default() as f64 / 1000.0
+            )))
+            .data(&latency)
+            .style(Style::default().fg(Color::Yellow)),
+        charts[1],
+    );
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[2]);
+    let mut outcomes: Vec<(&str, u64)> = board
+        .outcomes
+        .iter()
+        .map(|(name, count)| (name.as_str(), *count))
+        .collect();
+    outcomes.push(("pending", pending));
+    f.render_widget(
+        BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title("Status"))
+            .data(outcomes.as_slice())
+            .bar_width(15)
+            .bar_style(Style::default().fg(Color::Green)),
+        panels[0],
+    );
+    let slowest: Vec<ListItem> = board
+        .slowest
+        .iter()
+        .map(|(id, elapsed)| ListItem::new(format!("{id}  {}s", elapsed.as_secs())))
+        .collect();
+    f.render_widget(
+        List::new(slowest).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Slowest in flight"),
+        ),
+        panels[1],
+    );
+
+    let errors: Vec<ListItem> = board
+        .errors
+        .iter()
+        .rev()
+        .map(|e| ListItem::new(e.as_str()).style(Style::default().fg(Color::Red)))
+        .collect();
+    f.render_widget(
+        List::new(errors).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Recent errors"),
+        ),
+        rows[3],
+    );
+
+    f.render_widget(
+        Paragraph::new(
+            "p pause/resume submission  + faster  - slower  q stop submitting, twice to abort",
+        ),
+        rows[4],
+    );
+}
+
+/// Samples fitting in a chart of `width` columns
+fn recent(samples: &VecDeque<u64>, width: u16) -> Vec<u64> {
+    let width = usize::from(width.saturating_sub(2));
+    samples
+        .iter()
+        .skip(samples.len().saturating_sub(width))
+        .copied()
+        .collect()
+}
+
+/// Sends the warnings and errors to the dashboard while it is open, and every
+/// log to `inner` otherwise
+pub struct Logger {
+    inner: env_logger::Logger,
+}
+
+impl Logger {
+    pub fn new(inner: env_logger::Logger) -> Self {
+        Self { inner }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if a logger is already set
+    pub fn try_init(self) -> Result<(), log::SetLoggerError> {
+        log::set_max_level(self.inner.filter());
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &LogRecord<'_>) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        if !DASHBOARD.open.load(Ordering::Relaxed) {
+            self.inner.log(record);
+        } else if record.level() <= Level::Warn {
+            DASHBOARD
+                .board()
+                .error(crate::logger::redacted(record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}